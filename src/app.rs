@@ -1,6 +1,10 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseEvent, MouseEventKind,
+    },
+    cursor::SetCursorStyle,
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -9,54 +13,208 @@ use ratatui::{
     Terminal,
 };
 use std::{
-    io,
+    collections::BTreeMap,
+    fmt::Display,
+    io::{self, Stdout},
+    path::PathBuf,
+    str::FromStr,
     time::{Duration, Instant},
 };
-use tokio::sync::mpsc;
+use serde_json::{Map, Value};
+use tokio::{sync::mpsc, task::JoinHandle};
 
 use crate::{
-    gemini::GeminiClient,
-    ui::{ui, AppState},
+    clipboard::{self, Copied},
+    commands::{self, Command, CommandSpec},
+    config::Config,
+    editor::TextArea,
+    external_editor,
+    gemini::{self, GeminiClient, ModelInfo, Role},
+    history::{HistorySearch, InputHistory},
+    keymap::{self, Action, Context, KeyChord, Keymap},
+    markdown,
+    palette::{CommandPalette, PaletteEntry, PaletteItem},
+    session::{Session, SessionStore},
+    theme::Theme,
+    ui::{ui, AppState, ChatSelection, HelpOverlay, ModelPicker, Sidebar, SidebarMode},
+    vim::{Mode, Vim},
 };
 
+/// Chat lines moved per mouse wheel notch.
+const MOUSE_SCROLL_LINES: usize = 3;
+
 pub struct App {
     state: AppState,
     client: GeminiClient,
+    stream: bool,
+    personas: BTreeMap<String, String>,
+    sessions: SessionStore,
+    /// The conversation being saved to disk as it grows.
+    session: Session,
+    history: InputHistory,
+    /// The input as it was when Up/Down browsing started, paste chips and
+    /// all, given back once past the newest entry.
+    draft: Option<TextArea>,
+    /// An existing file `/export` would not replace without asking.
+    refused_export: Option<PathBuf>,
+    keymap: Keymap,
+    /// Name of the theme in use, and the config's colors laid over any theme.
+    theme_name: String,
+    colors: Map<String, Value>,
+    tx: mpsc::UnboundedSender<AppEvent>,
+    rx: mpsc::UnboundedReceiver<AppEvent>,
+    pending: Option<PendingRequest>,
+    next_request_id: u64,
+    should_quit: bool,
+    /// Set by Ctrl+E; the event loop owns the terminal, so it does the work.
+    open_editor: bool,
+}
+
+/// The request currently in flight. Events from any other request id are
+/// stale (e.g. arrived after the user pressed Esc) and get dropped.
+struct PendingRequest {
+    id: u64,
+    handle: JoinHandle<()>,
+}
+
+/// Raw mode, the alternate screen, mouse and bracketed paste: everything
+/// the UI needs, set up at start and after an external editor returns.
+fn enter_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    Ok(())
+}
+
+/// Gives the terminal back to the shell as it was before [`enter_terminal`].
+fn leave_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        SetCursorStyle::DefaultUserShape,
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
 }
 
 #[derive(Debug)]
 pub enum AppEvent {
-    Tick,
-    GeminiResponse(String),
-    GeminiError(String),
+    GeminiResponse(u64, String),
+    /// A piece of a streamed reply.
+    StreamChunk(u64, String),
+    /// The streamed reply is complete.
+    StreamFinished(u64),
+    GeminiError(u64, String),
+    ModelsLoaded(u64, Vec<ModelInfo>),
+    ModelsError(u64, String),
 }
 
 impl App {
-    pub fn new(api_key: String) -> Self {
-        Self {
-            state: AppState::default(),
-            client: GeminiClient::new(api_key),
-        }
+    pub fn new(
+        config: Config,
+        keymap: Keymap,
+        theme: Theme,
+        sessions: SessionStore,
+        session: Session,
+        history: InputHistory,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut client = GeminiClient::new(config.api_key);
+        client.model = gemini::resolve_model(&config.model);
+        client.generation_config = config.generation;
+        client.system_instruction = config
+            .persona
+            .as_ref()
+            .and_then(|name| config.personas.get(name))
+            .cloned();
+        let status_message = if session.messages.is_empty() {
+            AppState::default().status_message
+        } else {
+            format!("Resumed \"{}\" ({} messages) 📂", session.title, session.messages.len())
+        };
+        let state = AppState {
+            messages: session.messages.clone(),
+            session_id: session.id.clone(),
+            status_message,
+            model: client.model.clone(),
+            generation: client.generation_config.clone(),
+            persona: config.persona.filter(|_| client.system_instruction.is_some()),
+            vim: config.vim_mode.then(Vim::default),
+            theme,
+            reduced_motion: config.reduced_motion,
+            compact: config.compact,
+            ..AppState::default()
+        };
+        let mut app = Self {
+            state,
+            client,
+            stream: config.stream,
+            personas: config.personas,
+            sessions,
+            session,
+            history,
+            draft: None,
+            refused_export: None,
+            keymap,
+            theme_name: config.theme,
+            colors: config.colors,
+            tx,
+            rx,
+            pending: None,
+            next_request_id: 0,
+            should_quit: false,
+            open_editor: false,
+        };
+        app.state.input_placeholder = format!(
+            "Type your message here... ({} to send, {} for a new line, {} for help)",
+            app.key_name(Action::Send),
+            app.key_name(Action::Newline),
+            app.key_name(Action::Help),
+        );
+        app
+    }
+
+    /// The first key bound to `action`, for hints that follow the keymap.
+    fn key_name(&self, action: Action) -> String {
+        self.keymap
+            .chords(action)
+            .first()
+            .map_or_else(|| "unbound".to_string(), KeyChord::to_string)
     }
 
     pub async fn run(&mut self) -> Result<()> {
         // Setup terminal
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-
-        // Create channels for async communication
-        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        enter_terminal(&mut terminal)?;
 
         // Animation timer
         let mut last_tick = Instant::now();
         let tick_rate = Duration::from_millis(100);
 
+        // Vim mode gets a block cursor outside insert mode
+        let mut cursor_mode = None;
+
+        // Only draw after something happened, which with reduced motion
+        // leaves an idle screen alone entirely
+        let mut redraw = true;
+
         // Main event loop
         loop {
-            terminal.draw(|f| ui(f, &self.state))?;
+            if std::mem::take(&mut redraw) {
+                terminal.draw(|f| ui(f, &self.state))?;
+            }
+
+            let mode = self.state.vim.as_ref().map(|vim| vim.mode);
+            if mode != cursor_mode {
+                let style = match mode {
+                    Some(Mode::Normal | Mode::Visual) => SetCursorStyle::SteadyBlock,
+                    _ => SetCursorStyle::DefaultUserShape,
+                };
+                execute!(terminal.backend_mut(), style)?;
+                cursor_mode = mode;
+            }
 
             let timeout = tick_rate
                 .checked_sub(last_tick.elapsed())
@@ -64,98 +222,1116 @@ impl App {
 
             // Handle events
             if crossterm::event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == KeyEventKind::Press {
-                        match key.code {
-                            KeyCode::Char('c') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                                break;
-                            }
-                            KeyCode::Enter => {
-                                if !self.state.input.trim().is_empty() && !self.state.is_loading {
-                                    let message = self.state.input.clone();
-                                    self.state.add_message(message.clone(), true);
-                                    self.state.clear_input();
-                                    self.state.is_loading = true;
-                                    self.state.status_message = "Sending message to Gemini...".to_string();
-
-                                    // Send message to Gemini in background
-                                    let client = self.client.clone();
-                                    let tx_clone = tx.clone();
-                                    tokio::spawn(async move {
-                                        match client.send_message(&message).await {
-                                            Ok(response) => {
-                                                let _ = tx_clone.send(AppEvent::GeminiResponse(response));
-                                            }
-                                            Err(e) => {
-                                                let _ = tx_clone.send(AppEvent::GeminiError(e.to_string()));
-                                            }
-                                        }
-                                    });
+                redraw = true;
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key(key),
+                    Event::Mouse(mouse) => self.handle_mouse(mouse),
+                    Event::Paste(text) => self.handle_paste(&text),
+                    _ => {}
+                }
+            }
+
+            if self.should_quit {
+                break;
+            }
+
+            if std::mem::take(&mut self.open_editor) {
+                self.edit_externally(&mut terminal)?;
+                cursor_mode = None;
+                redraw = true;
+            }
+
+            // Handle async messages
+            while let Ok(event) = self.rx.try_recv() {
+                self.handle_app_event(event);
+                redraw = true;
+            }
+
+            // Handle animation ticks
+            if last_tick.elapsed() >= tick_rate {
+                if !self.state.reduced_motion {
+                    self.state.increment_animation();
+                    redraw = true;
+                }
+                last_tick = Instant::now();
+            }
+        }
+
+        self.shut_down();
+        leave_terminal(&mut terminal)
+    }
+
+    /// Saves the conversation on the way out, with a prompt still waiting
+    /// for its reply marked cancelled so `--resume` does not send it again.
+    fn shut_down(&mut self) {
+        self.cancel_turn();
+        self.save_session();
+    }
+
+    /// Hands the terminal to `$VISUAL` / `$EDITOR` with the input in a draft
+    /// file, then takes it back and loads the edited text into the input.
+    fn edit_externally(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+        leave_terminal(terminal)?;
+        let edited = external_editor::edit(&self.state.input.expanded_text());
+        enter_terminal(terminal)?;
+        terminal.clear()?;
+        self.leave_visual();
+
+        self.state.status_message = match edited {
+            Ok(text) if text.trim().is_empty() => {
+                self.state.input.clear();
+                "The edited message is empty".to_string()
+            }
+            Ok(text) => {
+                self.state.input.set_text(&text);
+                format!("Loaded the edited message ({} lines)", text.lines().count())
+            }
+            Err(e) => format!("Could not edit the message: {}", e),
+        };
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        let visual = self.state.vim.as_ref().is_some_and(|vim| vim.mode == Mode::Visual);
+        let before = visual.then(|| self.state.input.text());
+        self.dispatch_key(key);
+        // Editing keys that are not Vim's end the selection, whose anchor
+        // may no longer be inside the text
+        if before.is_some_and(|before| before != self.state.input.text()) {
+            self.leave_visual();
+        }
+    }
+
+    fn leave_visual(&mut self) {
+        if let Some(vim) = self.state.vim.as_mut().filter(|vim| vim.mode == Mode::Visual) {
+            vim.leave_visual(&mut self.state.input);
+        }
+    }
+
+    fn dispatch_key(&mut self, key: KeyEvent) {
+        let context = self.context();
+        if context == Context::Chat && self.handle_vim_key(&key) {
+            return;
+        }
+        let Some(action) = self.action(context, &key) else {
+            self.handle_text_key(context, key);
+            return;
+        };
+        match action.context() {
+            Context::Popup => self.run_popup_action(action),
+            Context::Browser => self.run_browser_action(action),
+            Context::Search => self.run_search_action(action),
+            Context::Copy => self.run_copy_action(action),
+            Context::Global | Context::Chat | Context::Editor => self.run_action(action),
+        }
+    }
+
+    /// Vim mode gets the first look at keys in the chat view: the Cancel key
+    /// leaves insert and visual mode before it cancels anything, and outside
+    /// insert mode characters are commands. Returns whether it used the key.
+    fn handle_vim_key(&mut self, key: &KeyEvent) -> bool {
+        let cancel = self.keymap.action(&[Context::Chat], key) == Some(Action::Cancel);
+        let Some(vim) = self.state.vim.as_mut() else {
+            return false;
+        };
+        if cancel {
+            return vim.escape(&mut self.state.input);
+        }
+        match key.code {
+            KeyCode::Char(c) if vim.mode != Mode::Insert && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                vim.key(c, &mut self.state.input);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Which bindings apply to the view that has the keyboard.
+    fn context(&self) -> Context {
+        let sidebar_mode = self
+            .state
+            .sidebar
+            .as_ref()
+            .filter(|sidebar| sidebar.focused)
+            .map(|sidebar| &sidebar.mode);
+        if self.state.model_picker.is_some() || self.state.palette.is_some() || self.state.help.is_some() {
+            Context::Popup
+        } else if let Some(mode) = sidebar_mode {
+            match mode {
+                SidebarMode::Browse => Context::Browser,
+                // Filtering, renaming and confirming are prompts
+                _ => Context::Popup,
+            }
+        } else if self.state.history_search.is_some() {
+            Context::Search
+        } else if self.state.selection.is_some() {
+            Context::Copy
+        } else {
+            Context::Chat
+        }
+    }
+
+    /// The action `key` triggers in `context`, falling back to the global
+    /// bindings. Plain characters are left alone while something is being
+    /// typed, so `?` and `q` still reach the text.
+    fn action(&self, context: Context, key: &KeyEvent) -> Option<Action> {
+        if KeyChord::new(key.code, key.modifiers).is_text() && self.is_typing() {
+            return None;
+        }
+        match context {
+            Context::Chat => self.keymap.action(&[Context::Chat, Context::Editor, Context::Global], key),
+            context => self.keymap.action(&[context, Context::Global], key),
+        }
+    }
+
+    /// Whether plain characters are text right now. The chat input always
+    /// takes them, so a message can start with `?` or any other bound key.
+    fn is_typing(&self) -> bool {
+        let sidebar_prompt = self.state.sidebar.as_ref().is_some_and(|sidebar| {
+            sidebar.focused && matches!(sidebar.mode, SidebarMode::Filter | SidebarMode::Rename(_))
+        });
+        let chat_input = self.context() == Context::Chat;
+        self.state.palette.is_some() || self.state.history_search.is_some() || sidebar_prompt || chat_input
+    }
+
+    /// Keys no binding claims: text for whatever is being typed into.
+    fn handle_text_key(&mut self, context: Context, key: KeyEvent) {
+        let text = match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => Some(c),
+            KeyCode::Backspace => None,
+            _ => return,
+        };
+
+        match context {
+            Context::Chat => {
+                if let Some(c) = text {
+                    self.state.input.insert_char(c);
+                }
+            }
+            Context::Search => {
+                if let Some(mut search) = self.state.history_search.take() {
+                    match text {
+                        Some(c) => search.query.push(c),
+                        None => {
+                            search.query.pop();
+                        }
+                    }
+                    self.search_history_from_newest(&mut search);
+                    self.state.history_search = Some(search);
+                }
+            }
+            Context::Popup => {
+                if let Some(palette) = self.state.palette.as_mut() {
+                    let mut query = palette.query.clone();
+                    match text {
+                        Some(c) => query.push(c),
+                        None => {
+                            query.pop();
+                        }
+                    }
+                    palette.set_query(query);
+                } else if let Some(sidebar) = self.state.sidebar.as_mut() {
+                    let selected_id = sidebar.selected_session().map(|session| session.id.clone());
+                    match &mut sidebar.mode {
+                        SidebarMode::Filter => {
+                            match text {
+                                Some(c) => sidebar.filter.push(c),
+                                None => {
+                                    sidebar.filter.pop();
                                 }
                             }
-                            KeyCode::Char(c) => {
-                                self.state.insert_char(c);
-                            }
-                            KeyCode::Backspace => {
-                                self.state.delete_char();
-                            }
-                            KeyCode::Left => {
-                                self.state.move_cursor_left();
+                            sidebar.selected = 0;
+                        }
+                        SidebarMode::Rename(draft) => match text {
+                            Some(c) => draft.push(c),
+                            None => {
+                                draft.pop();
                             }
-                            KeyCode::Right => {
-                                self.state.move_cursor_right();
+                        },
+                        SidebarMode::ConfirmDelete => {
+                            sidebar.mode = SidebarMode::Browse;
+                            if let (Some('y'), Some(id)) = (text, selected_id) {
+                                self.delete_session(&id);
                             }
-                            KeyCode::Esc => {
-                                if self.state.is_loading {
-                                    self.state.is_loading = false;
-                                    self.state.status_message = "Message cancelled".to_string();
-                                }
-                            }
-                            _ => {}
                         }
+                        SidebarMode::Browse => {}
                     }
                 }
             }
+            Context::Global | Context::Editor | Context::Copy | Context::Browser => {}
+        }
+    }
 
-            // Handle async messages
-            while let Ok(event) = rx.try_recv() {
-                match event {
-                    AppEvent::GeminiResponse(response) => {
-                        self.state.add_message(response, false);
-                        self.state.is_loading = false;
-                        self.state.status_message = "Response received! 🎉".to_string();
-                    }
-                    AppEvent::GeminiError(error) => {
-                        self.state.add_message(
-                            format!("❌ Error: {}", error),
-                            false,
-                        );
-                        self.state.is_loading = false;
-                        self.state.status_message = "Error occurred 😞".to_string();
+    /// Does what a global or chat binding, or a palette entry, asks for.
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::Send if commands::is_command(self.state.input.text().trim_start()) => {
+                let line = self.state.input.text().trim().to_string();
+                self.state.input.clear();
+                self.remember_input(&line);
+                self.run_command(&line);
+            }
+            Action::Send if !self.state.input.text().trim().is_empty() && !self.state.is_loading => {
+                let typed = self.state.input.expanded_text().trim_end().to_string();
+                self.remember_input(&typed);
+                let message = commands::unescape(&typed);
+                self.state.scroll_to_bottom();
+                self.state.add_message(message, Role::User);
+                self.state.input.clear();
+                self.send_conversation();
+            }
+            Action::Send => {}
+            Action::Newline => self.state.input.insert_newline(),
+            Action::Complete if commands::is_command(&self.state.input.text()) => self.complete_command(),
+            Action::Complete => {
+                if let Some(sidebar) = self.state.sidebar.as_mut() {
+                    sidebar.focused = true;
+                }
+            }
+            Action::Cancel if self.pending.is_some() => {
+                self.cancel_turn();
+                self.state.status_message = "Message cancelled".to_string();
+                self.save_session();
+            }
+            Action::Cancel => {}
+            Action::Help if self.state.help.is_some() => self.state.help = None,
+            Action::Help => {
+                self.close_popups();
+                self.state.help = Some(HelpOverlay::new(self.keymap.bindings(), self.state.vim.is_some()));
+            }
+            Action::CommandPalette => {
+                self.close_popups();
+                self.open_palette();
+            }
+            Action::ToggleSidebar => {
+                self.close_popups();
+                self.toggle_sidebar();
+            }
+            Action::Quit => self.should_quit = true,
+            Action::CopyMode => self.start_selection(),
+            Action::ExternalEditor => self.open_editor = true,
+            Action::HistorySearch => self.state.history_search = Some(HistorySearch::default()),
+            Action::HistoryPrevious => self.recall_previous(),
+            Action::HistoryNext => self.recall_next(),
+            Action::ScrollPageUp => self.state.scroll_page_up(),
+            Action::ScrollPageDown => self.state.scroll_page_down(),
+            Action::ScrollTop => self.state.scroll_to_top(),
+            Action::ScrollBottom => self.state.scroll_to_bottom(),
+            Action::MoveLeft => self.state.input.move_left(),
+            Action::MoveRight => self.state.input.move_right(),
+            Action::MoveUp if self.state.input.is_on_first_line() => self.recall_previous(),
+            Action::MoveUp => self.state.input.move_up(),
+            Action::MoveDown if self.state.input.is_on_last_line() => self.recall_next(),
+            Action::MoveDown => self.state.input.move_down(),
+            Action::WordLeft => self.state.input.move_word_left(),
+            Action::WordRight => self.state.input.move_word_right(),
+            Action::LineStart if self.state.input.is_empty() => self.state.scroll_to_top(),
+            Action::LineStart => self.state.input.move_home(),
+            Action::LineEnd if self.state.input.is_empty() => self.state.scroll_to_bottom(),
+            Action::LineEnd => self.state.input.move_end(),
+            Action::DeleteBackward => self.state.input.backspace(),
+            Action::DeleteForward => self.state.input.delete(),
+            Action::DeleteWord => self.state.input.delete_word_before(),
+            Action::DeleteToLineStart => self.state.input.delete_to_line_start(),
+            // The other views run their own actions
+            _ => {}
+        }
+    }
+
+    /// Lists every chat action and slash command in the Ctrl+K popup.
+    fn open_palette(&mut self) {
+        let mut entries: Vec<PaletteEntry> = self
+            .keymap
+            .bindings()
+            .into_iter()
+            .filter(|(action, _)| matches!(action.context(), Context::Global | Context::Chat))
+            .map(|(action, chords)| PaletteEntry {
+                item: PaletteItem::Action(action),
+                label: action.label().to_string(),
+                // Only keys that work from the input box, where the palette opens
+                shortcut: keymap::describe(&chords.into_iter().filter(|chord| !chord.is_text()).collect::<Vec<_>>()),
+            })
+            .collect();
+        entries.extend(commands::COMMANDS.iter().map(|spec| PaletteEntry {
+            item: PaletteItem::Command(spec),
+            label: spec.about.to_string(),
+            shortcut: format!("/{}", spec.name),
+        }));
+        self.state.palette = Some(CommandPalette::new(entries));
+    }
+
+    fn close_popups(&mut self) {
+        self.state.help = None;
+        self.state.palette = None;
+        self.state.model_picker = None;
+    }
+
+    /// Keys for whichever popup or prompt is open: the model picker, the
+    /// palette, the help overlay or a conversation browser prompt.
+    fn run_popup_action(&mut self, action: Action) {
+        if self.state.model_picker.is_some() {
+            self.run_model_picker_action(action);
+        } else if self.state.palette.is_some() {
+            self.run_palette_action(action);
+        } else if let Some(help) = self.state.help.as_mut() {
+            match action {
+                Action::PopupPrevious => help.scroll_up(),
+                Action::PopupNext => help.scroll_down(),
+                _ => self.state.help = None,
+            }
+        } else {
+            self.run_sidebar_prompt_action(action);
+        }
+    }
+
+    fn run_palette_action(&mut self, action: Action) {
+        let Some(palette) = self.state.palette.as_mut() else {
+            return;
+        };
+
+        match action {
+            Action::PopupPrevious => palette.select_previous(),
+            Action::PopupNext => palette.select_next(),
+            Action::PopupChoose => {
+                let item = palette.selected_item();
+                self.state.palette = None;
+                match item {
+                    Some(PaletteItem::Action(action)) => self.run_action(action),
+                    // Commands without arguments run; the rest wait for them
+                    Some(PaletteItem::Command(spec)) if spec.usage.is_empty() => {
+                        self.run_command(&format!("/{}", spec.name));
                     }
-                    AppEvent::Tick => {
-                        self.state.increment_animation();
+                    Some(PaletteItem::Command(spec)) => {
+                        self.state.input.set_text(&format!("/{} ", spec.name));
                     }
+                    None => {}
                 }
             }
+            _ => self.state.palette = None,
+        }
+    }
 
-            // Handle animation ticks
-            if last_tick.elapsed() >= tick_rate {
-                self.state.increment_animation();
-                last_tick = Instant::now();
+    fn remember_input(&mut self, entry: &str) {
+        self.draft = None;
+        if let Err(e) = self.history.push(entry) {
+            self.state.status_message = format!("Could not save input history: {}", e);
+        }
+    }
+
+    fn recall_previous(&mut self) {
+        let current = self.state.input.text();
+        let starting = !self.history.is_browsing();
+        if let Some(entry) = self.history.previous(&current) {
+            if starting {
+                self.draft = Some(self.state.input.clone());
+            }
+            self.state.input.set_text(entry);
+        }
+    }
+
+    fn recall_next(&mut self) {
+        let Some(entry) = self.history.next() else { return };
+        match self.draft.take() {
+            Some(draft) if !self.history.is_browsing() => self.state.input = draft,
+            draft => {
+                self.draft = draft;
+                self.state.input.set_text(&entry);
             }
         }
+    }
 
-        // Restore terminal
-        disable_raw_mode()?;
-        execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )?;
-        terminal.show_cursor()?;
+    /// Bindings while Ctrl+R is active; typing narrows the search.
+    fn run_search_action(&mut self, action: Action) {
+        let Some(mut search) = self.state.history_search.take() else {
+            return;
+        };
 
-        Ok(())
+        match action {
+            Action::SearchOlder => {
+                if let Some((index, entry)) = self.history.search(&search.query, search.found) {
+                    search.found = Some(index);
+                    search.preview = entry.to_string();
+                }
+            }
+            Action::SearchAccept => {
+                if search.found.is_some() {
+                    self.state.input.set_text(&search.preview);
+                }
+                return;
+            }
+            Action::SearchCancel => return,
+            _ => {}
+        }
+
+        self.state.history_search = Some(search);
+    }
+
+    fn search_history_from_newest(&self, search: &mut HistorySearch) {
+        match self.history.search(&search.query, None) {
+            Some((index, entry)) if !search.query.is_empty() => {
+                search.found = Some(index);
+                search.preview = entry.to_string();
+            }
+            _ => {
+                search.found = None;
+                search.preview.clear();
+            }
+        }
+    }
+
+    /// Starts picking something to copy, beginning with the newest message.
+    fn start_selection(&mut self) {
+        let Some(last) = self.state.messages.len().checked_sub(1) else {
+            self.state.status_message = "Nothing to copy yet".to_string();
+            return;
+        };
+        self.state.select(ChatSelection { message: last, code_block: None });
+        self.state.status_message = format!(
+            "Copy: {}/{} pick a message · {} pick a code block · {} copy · {} cancel",
+            self.key_name(Action::CopyPrevious),
+            self.key_name(Action::CopyNext),
+            self.key_name(Action::CopyNextBlock),
+            self.key_name(Action::CopyConfirm),
+            self.key_name(Action::CopyCancel),
+        );
+    }
+
+    fn run_copy_action(&mut self, action: Action) {
+        let Some(mut selection) = self.state.selection else {
+            return;
+        };
+        let count = self.state.messages.len();
+        let blocks = self
+            .state
+            .messages
+            .get(selection.message)
+            .map_or(0, |message| markdown::code_blocks(&message.content).len());
+
+        match action {
+            Action::CopyPrevious if selection.message > 0 => {
+                selection = ChatSelection { message: selection.message - 1, code_block: None };
+            }
+            Action::CopyNext if selection.message + 1 < count => {
+                selection = ChatSelection { message: selection.message + 1, code_block: None };
+            }
+            // Cycle whole message → first block → … → last block → whole message
+            Action::CopyNextBlock if blocks > 0 => {
+                selection.code_block = match selection.code_block {
+                    None => Some(0),
+                    Some(block) if block + 1 < blocks => Some(block + 1),
+                    Some(_) => None,
+                };
+            }
+            Action::CopyPreviousBlock if blocks > 0 => {
+                selection.code_block = match selection.code_block {
+                    None => Some(blocks - 1),
+                    Some(0) => None,
+                    Some(block) => Some(block - 1),
+                };
+            }
+            Action::CopyConfirm => {
+                self.state.selection = None;
+                self.copy_selection(selection);
+                return;
+            }
+            Action::CopyCancel => {
+                self.state.selection = None;
+                self.state.status_message = "Copy cancelled".to_string();
+                return;
+            }
+            _ => return,
+        }
+        self.state.select(selection);
+    }
+
+    fn copy_selection(&mut self, selection: ChatSelection) {
+        let Some(message) = self.state.messages.get(selection.message) else {
+            return;
+        };
+        let (what, text) = match selection.code_block {
+            Some(block) => match markdown::code_blocks(&message.content).into_iter().nth(block) {
+                Some(code) => ("code block", code),
+                None => return,
+            },
+            None => ("message", message.content.clone()),
+        };
+
+        self.state.status_message = match clipboard::copy(&text) {
+            Ok(Copied::Clipboard) => format!("📋 Copied {} to the clipboard", what),
+            Ok(Copied::File(path)) => format!("📋 Clipboard unavailable; saved {} to {}", what, path.display()),
+            Err(e) => format!("Could not copy {}: {}", what, e),
+        };
+    }
+
+    /// A bracketed paste arrives as one event, so embedded newlines never
+    /// send a half-pasted message.
+    fn handle_paste(&mut self, text: &str) {
+        if let Some(sidebar) = self.state.sidebar.as_mut().filter(|sidebar| sidebar.focused) {
+            // Only the filter and rename boxes take text; keep it to one line
+            let line = text.lines().next().unwrap_or_default();
+            match &mut sidebar.mode {
+                SidebarMode::Filter => sidebar.filter.push_str(line),
+                SidebarMode::Rename(draft) => draft.push_str(line),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(mut search) = self.state.history_search.take() {
+            search.query.push_str(text.lines().next().unwrap_or_default());
+            self.search_history_from_newest(&mut search);
+            self.state.history_search = Some(search);
+            return;
+        }
+        if self.state.model_picker.is_none() {
+            self.leave_visual();
+            self.state.input.paste(text);
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.state.scroll_up(MOUSE_SCROLL_LINES),
+            MouseEventKind::ScrollDown => self.state.scroll_down(MOUSE_SCROLL_LINES),
+            _ => {}
+        }
+    }
+
+    fn toggle_sidebar(&mut self) {
+        if self.state.sidebar.take().is_some() {
+            return;
+        }
+
+        // Make sure the conversation on screen shows up in the list
+        self.save_session();
+        match self.sessions.list() {
+            Ok(sessions) => {
+                let mut sidebar = Sidebar::new(sessions);
+                sidebar.selected = sidebar
+                    .visible()
+                    .iter()
+                    .position(|session| session.id == self.session.id)
+                    .unwrap_or(0);
+                self.state.sidebar = Some(sidebar);
+            }
+            Err(e) => {
+                self.state.status_message = format!("Could not list conversations: {}", e);
+            }
+        }
+    }
+
+    fn refresh_sidebar(&mut self) {
+        let sessions = match self.sessions.list() {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                self.state.status_message = format!("Could not list conversations: {}", e);
+                return;
+            }
+        };
+        if let Some(sidebar) = self.state.sidebar.as_mut() {
+            sidebar.set_sessions(sessions);
+        }
+    }
+
+    fn run_browser_action(&mut self, action: Action) {
+        let Some(sidebar) = self.state.sidebar.as_mut() else {
+            return;
+        };
+        let selected_id = sidebar.selected_session().map(|session| session.id.clone());
+
+        match action {
+            Action::BrowserPrevious => sidebar.select_previous(),
+            Action::BrowserNext => sidebar.select_next(),
+            Action::BrowserFilter => sidebar.mode = SidebarMode::Filter,
+            Action::BrowserRename => {
+                if let Some(session) = sidebar.selected_session() {
+                    sidebar.mode = SidebarMode::Rename(session.title.clone());
+                }
+            }
+            Action::BrowserDelete if selected_id.is_some() => sidebar.mode = SidebarMode::ConfirmDelete,
+            Action::BrowserClose => sidebar.focused = false,
+            Action::BrowserOpen => {
+                if let Some(id) = selected_id {
+                    self.open_session(&id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Enter and Esc in the browser's filter, rename and delete prompts;
+    /// typing goes through [`App::handle_text_key`].
+    fn run_sidebar_prompt_action(&mut self, action: Action) {
+        let Some(sidebar) = self.state.sidebar.as_mut() else {
+            return;
+        };
+        let selected_id = sidebar.selected_session().map(|session| session.id.clone());
+
+        match (sidebar.mode.clone(), action) {
+            (SidebarMode::Filter, Action::PopupChoose) => sidebar.mode = SidebarMode::Browse,
+            (SidebarMode::Filter, Action::PopupClose) => {
+                sidebar.filter.clear();
+                sidebar.selected = 0;
+                sidebar.mode = SidebarMode::Browse;
+            }
+            (SidebarMode::Rename(_), Action::PopupClose) => sidebar.mode = SidebarMode::Browse,
+            (SidebarMode::Rename(draft), Action::PopupChoose) => {
+                sidebar.mode = SidebarMode::Browse;
+                let title = draft.trim().to_string();
+                if let (Some(id), false) = (selected_id, title.is_empty()) {
+                    self.rename_session(&id, &title);
+                }
+            }
+            // Only `y` confirms a delete; any other key backs out
+            (SidebarMode::ConfirmDelete, _) => sidebar.mode = SidebarMode::Browse,
+            _ => {}
+        }
+    }
+
+    /// Switches the chat to a saved conversation.
+    fn open_session(&mut self, id: &str) {
+        if id != self.session.id {
+            let session = match self.sessions.load(id) {
+                Ok(session) => session,
+                Err(e) => {
+                    self.state.status_message = format!("Could not open conversation: {}", e);
+                    return;
+                }
+            };
+
+            self.cancel_turn();
+            self.save_session();
+
+            self.state.messages = session.messages.clone();
+            self.state.selection = None;
+            self.state.scroll_to_bottom();
+            self.state.session_id = session.id.clone();
+            self.state.status_message = format!("Opened \"{}\" 📂", session.title);
+            self.session = session;
+        }
+
+        if let Some(sidebar) = self.state.sidebar.as_mut() {
+            sidebar.focused = false;
+        }
+    }
+
+    fn rename_session(&mut self, id: &str, title: &str) {
+        if id == self.session.id {
+            self.session.title = title.to_string();
+            self.save_session();
+        } else if let Err(e) = self.sessions.rename(id, title) {
+            self.state.status_message = format!("Could not rename conversation: {}", e);
+            return;
+        }
+        self.state.status_message = format!("Renamed to \"{}\"", title);
+        self.refresh_sidebar();
+    }
+
+    fn delete_session(&mut self, id: &str) {
+        if let Err(e) = self.sessions.delete(id) {
+            self.state.status_message = format!("Could not delete conversation: {}", e);
+            return;
+        }
+
+        // Deleting the open conversation starts a fresh one
+        if id == self.session.id {
+            self.cancel_turn();
+            self.session = self.sessions.new_session();
+            self.state.messages.clear();
+            self.state.scroll_to_bottom();
+            self.state.session_id = self.session.id.clone();
+        }
+        self.state.status_message = "Conversation deleted 🗑️".to_string();
+        self.refresh_sidebar();
+    }
+
+    /// Writes the conversation to disk; failures only show in the status bar.
+    fn save_session(&mut self) {
+        self.session.messages.clone_from(&self.state.messages);
+        if let Err(e) = self.sessions.save(&mut self.session) {
+            self.state.status_message = format!("Could not save conversation: {}", e);
+        }
+    }
+
+    /// Handles a `/command` typed into the input box.
+    fn run_command(&mut self, line: &str) {
+        let (name, args) = commands::split(line);
+        let Some(spec) = commands::find(name) else {
+            self.state.status_message = format!("Unknown command: /{} (try /help, or // to send it as a message)", name);
+            return;
+        };
+
+        match spec.command {
+            Command::Help => self.run_action(Action::Help),
+            Command::Clear => self.start_new_conversation(),
+            Command::Save => self.save_now(args),
+            Command::Load if args.is_empty() => {
+                if self.state.sidebar.is_none() {
+                    self.toggle_sidebar();
+                }
+                if let Some(sidebar) = self.state.sidebar.as_mut() {
+                    sidebar.focused = true;
+                }
+            }
+            Command::Load => self.load_matching(args),
+            Command::Export => self.export_conversation(args),
+            Command::Edit => self.open_editor = true,
+            Command::Models => self.open_model_picker(),
+            Command::Model if args.is_empty() => self.open_model_picker(),
+            Command::Model => self.set_model(args),
+            Command::Temperature | Command::TopP | Command::TopK | Command::MaxTokens | Command::Stop
+                if args.is_empty() =>
+            {
+                self.state.status_message = format!("Sampling: {}", self.client.generation_config.summary());
+            }
+            Command::Temperature | Command::TopP | Command::TopK | Command::MaxTokens | Command::Stop => {
+                self.set_generation_param(spec, args);
+            }
+            Command::Persona if args.is_empty() => {
+                let names: Vec<&str> = self.personas.keys().map(String::as_str).collect();
+                self.state.status_message = if names.is_empty() {
+                    "No personas configured; add some under \"personas\" in the config file".to_string()
+                } else {
+                    format!("Personas: {} (use /persona NAME or /persona none)", names.join(", "))
+                };
+            }
+            Command::Persona => self.set_persona(args),
+            Command::Theme if args.is_empty() => {
+                self.state.status_message =
+                    format!("Themes: {} (now {})", Theme::names().join(", "), self.theme_name);
+            }
+            Command::Theme => self.set_theme(args),
+            Command::Quit => self.should_quit = true,
+        }
+    }
+
+    /// Tab on a `/command` line completes the command name or its argument.
+    fn complete_command(&mut self) {
+        let input = self.state.input.text();
+        let (name, _) = commands::split(&input);
+        let arguments = match commands::find(name).map(|spec| spec.command) {
+            Some(Command::Model) => gemini::MODEL_ALIASES.iter().map(|(alias, _)| alias.to_string()).collect(),
+            Some(Command::Persona) => {
+                let mut names: Vec<String> = self.personas.keys().cloned().collect();
+                names.push("none".to_string());
+                names
+            }
+            Some(Command::Theme) => Theme::names(),
+            Some(Command::Load) => self.sessions.list()
+                .map(|sessions| sessions.into_iter().map(|session| session.id).collect())
+                .unwrap_or_default(),
+            Some(Command::Temperature | Command::TopP | Command::TopK | Command::MaxTokens | Command::Stop) => {
+                vec!["default".to_string()]
+            }
+            _ => Vec::new(),
+        };
+        if let Some(completed) = commands::complete(&input, &arguments) {
+            self.state.input.set_text(&completed);
+        }
+    }
+
+    /// Leaves the current conversation on disk and starts an empty one.
+    fn start_new_conversation(&mut self) {
+        self.cancel_turn();
+        self.save_session();
+
+        self.session = self.sessions.new_session();
+        self.state.messages.clear();
+        self.state.selection = None;
+        self.state.scroll_to_bottom();
+        self.state.session_id = self.session.id.clone();
+        self.state.status_message = "Started a new conversation ✨".to_string();
+        self.refresh_sidebar();
+    }
+
+    fn save_now(&mut self, title: &str) {
+        if self.state.messages.is_empty() {
+            self.state.status_message = "Nothing to save yet".to_string();
+            return;
+        }
+        if !title.is_empty() {
+            self.session.title = title.to_string();
+        }
+        self.session.messages.clone_from(&self.state.messages);
+        self.state.status_message = match self.sessions.save(&mut self.session) {
+            Ok(()) => format!("Saved \"{}\" ({}) 💾", self.session.title, self.session.id),
+            Err(e) => format!("Could not save conversation: {}", e),
+        };
+        self.refresh_sidebar();
+    }
+
+    fn export_conversation(&mut self, path: &str) {
+        if self.state.messages.is_empty() {
+            self.state.status_message = "Nothing to export yet".to_string();
+            return;
+        }
+        self.session.messages.clone_from(&self.state.messages);
+        let path = match self.session.export_path(Some(path).filter(|path| !path.is_empty())) {
+            Ok(path) => path,
+            Err(e) => {
+                self.state.status_message = format!("Could not export conversation: {}", e);
+                return;
+            }
+        };
+        // Exporting to the same existing file twice in a row confirms replacing it
+        let overwrite = self.refused_export.take().is_some_and(|refused| refused == path);
+        if path.exists() && !overwrite {
+            self.state.status_message =
+                format!("{} already exists; run the same /export again to replace it", path.display());
+            self.refused_export = Some(path);
+            return;
+        }
+        self.state.status_message = match self.session.export(&path, overwrite) {
+            Ok(()) => format!("Exported to {} 📝", path.display()),
+            Err(e) => format!("Could not export conversation: {}", e),
+        };
+    }
+
+    /// Opens the saved conversation with this id, or the newest one whose
+    /// title contains `query`.
+    fn load_matching(&mut self, query: &str) {
+        let sessions = match self.sessions.list() {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                self.state.status_message = format!("Could not list conversations: {}", e);
+                return;
+            }
+        };
+        let query_lower = query.to_lowercase();
+        let found = sessions
+            .iter()
+            .find(|session| session.id == query)
+            .or_else(|| sessions.iter().find(|session| session.title.to_lowercase().contains(&query_lower)));
+        match found {
+            Some(session) => {
+                let id = session.id.clone();
+                self.open_session(&id);
+            }
+            None => self.state.status_message = format!("No saved conversation matches \"{}\"", query),
+        }
+    }
+
+    fn set_model(&mut self, name: &str) {
+        self.client.model = gemini::resolve_model(name);
+        self.state.model = self.client.model.clone();
+        self.state.status_message = format!("Switched to {} 🔀", self.client.model);
+    }
+
+    /// Switches the system instruction for the following turns; `none`
+    /// goes back to plain Gemini.
+    fn set_persona(&mut self, name: &str) {
+        if name.eq_ignore_ascii_case("none") {
+            self.client.system_instruction = None;
+            self.state.persona = None;
+            self.state.status_message = "Persona cleared".to_string();
+            return;
+        }
+
+        match self.personas.get(name) {
+            Some(instruction) => {
+                self.client.system_instruction = Some(instruction.clone());
+                self.state.persona = Some(name.to_string());
+                self.state.status_message = format!("Now answering as {} 🎭", name);
+            }
+            None => {
+                self.state.status_message = format!("Unknown persona: {}", name);
+            }
+        }
+    }
+
+    /// Redraws everything in another theme, keeping the config's own colors.
+    fn set_theme(&mut self, name: &str) {
+        match Theme::load(name).and_then(|theme| theme.with_colors(&self.colors)) {
+            Ok(theme) => {
+                self.state.theme = theme;
+                self.theme_name = name.to_string();
+                self.state.status_message = format!("Switched to the {} theme 🎨", name);
+            }
+            Err(e) => self.state.status_message = format!("Could not load theme: {:#}", e),
+        }
+    }
+
+    /// Adjusts one sampling parameter for the rest of the session.
+    /// `default` unsets it again.
+    fn set_generation_param(&mut self, spec: &CommandSpec, args: &str) {
+        let mut generation = self.client.generation_config.clone();
+        let result = match spec.command {
+            Command::Temperature => parse_param(args).map(|value| generation.temperature = value),
+            Command::TopP => parse_param(args).map(|value| generation.top_p = value),
+            Command::TopK => parse_param(args).map(|value| generation.top_k = value),
+            Command::MaxTokens => parse_param(args).map(|value| generation.max_output_tokens = value),
+            _ => {
+                if args.eq_ignore_ascii_case("default") {
+                    generation.stop_sequences.clear();
+                } else {
+                    generation.stop_sequences.push(args.to_string());
+                }
+                Ok(())
+            }
+        }
+        .and_then(|_| generation.validate());
+
+        match result {
+            Ok(()) => {
+                self.state.status_message = format!("Sampling: {}", generation.summary());
+                self.state.generation = generation.clone();
+                self.client.generation_config = generation;
+            }
+            Err(e) => {
+                self.state.status_message = format!("Invalid /{}: {}", spec.name, e);
+            }
+        }
+    }
+
+    /// Opens the model popup and fetches the list in the background.
+    fn open_model_picker(&mut self) {
+        self.next_request_id += 1;
+        let id = self.next_request_id;
+        self.state.model_picker = Some(ModelPicker { request_id: id, ..ModelPicker::default() });
+        self.state.status_message = "Fetching available models...".to_string();
+
+        let client = self.client.clone();
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let event = match client.list_models().await {
+                Ok(models) => AppEvent::ModelsLoaded(id, models),
+                Err(e) => AppEvent::ModelsError(id, e.to_string()),
+            };
+            let _ = tx.send(event);
+        });
+    }
+
+    fn run_model_picker_action(&mut self, action: Action) {
+        let Some(picker) = self.state.model_picker.as_mut() else {
+            return;
+        };
+
+        match action {
+            Action::PopupPrevious => picker.select_previous(),
+            Action::PopupNext => picker.select_next(),
+            Action::PopupChoose => {
+                if let Some(model) = picker.selected_model() {
+                    let id = model.id().to_string();
+                    self.state.model_picker = None;
+                    self.set_model(&id);
+                }
+            }
+            Action::PopupClose => {
+                self.state.model_picker = None;
+                self.state.status_message = format!("Still using {}", self.client.model);
+            }
+            _ => {}
+        }
+    }
+
+    /// Sends the whole conversation to Gemini in the background.
+    fn send_conversation(&mut self) {
+        self.state.is_loading = true;
+        self.state.status_message = "Sending message to Gemini...".to_string();
+
+        self.next_request_id += 1;
+        let id = self.next_request_id;
+        let history = self.state.conversation();
+        let client = self.client.clone();
+        let stream = self.stream;
+        let tx = self.tx.clone();
+
+        let handle = tokio::spawn(async move {
+            let result = if stream {
+                let chunk_tx = tx.clone();
+                client
+                    .stream_message(&history, |chunk| {
+                        let _ = chunk_tx.send(AppEvent::StreamChunk(id, chunk));
+                    })
+                    .await
+                    .map(|_| AppEvent::StreamFinished(id))
+            } else {
+                client
+                    .send_message(&history)
+                    .await
+                    .map(|response| AppEvent::GeminiResponse(id, response))
+            };
+
+            let event = result.unwrap_or_else(|e| AppEvent::GeminiError(id, e.to_string()));
+            let _ = tx.send(event);
+        });
+
+        self.pending = Some(PendingRequest { id, handle });
+    }
+
+    /// Aborts the in-flight request, if any, and marks the prompt it was
+    /// answering as cancelled. Finished turns are left alone.
+    fn cancel_turn(&mut self) {
+        if self.pending.is_some() {
+            self.cancel_request();
+            self.state.cancel_pending_turn();
+        }
+    }
+
+    /// Aborts the in-flight HTTP request, if any.
+    fn cancel_request(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.handle.abort();
+        }
+    }
+
+    fn handle_app_event(&mut self, event: AppEvent) {
+        let current = match &event {
+            AppEvent::GeminiResponse(id, _)
+            | AppEvent::StreamChunk(id, _)
+            | AppEvent::StreamFinished(id)
+            | AppEvent::GeminiError(id, _) => self.pending.as_ref().is_some_and(|pending| pending.id == *id),
+            // The picker may have been closed, or reopened, while the list was loading
+            AppEvent::ModelsLoaded(id, _) | AppEvent::ModelsError(id, _) => {
+                self.state.model_picker.as_ref().is_some_and(|picker| picker.request_id == *id)
+            }
+        };
+        if !current {
+            return;
+        }
+
+        match event {
+            AppEvent::GeminiResponse(_, response) => {
+                self.pending = None;
+                self.state.add_message(response, Role::Model);
+                self.state.is_loading = false;
+                self.state.status_message = "Response received! 🎉".to_string();
+                self.save_session();
+            }
+            AppEvent::StreamChunk(_, chunk) => {
+                self.state.append_stream_chunk(&chunk);
+                self.state.status_message = "Gemini is typing...".to_string();
+            }
+            AppEvent::StreamFinished(_) => {
+                self.pending = None;
+                self.state.finish_stream();
+                self.state.is_loading = false;
+                self.state.status_message = "Response received! 🎉".to_string();
+                self.save_session();
+            }
+            AppEvent::GeminiError(_, error) => {
+                self.pending = None;
+                self.state.finish_stream();
+                self.state.add_error(format!("❌ Error: {}", error));
+                self.state.is_loading = false;
+                self.state.status_message = "Error occurred 😞".to_string();
+                self.save_session();
+            }
+            AppEvent::ModelsLoaded(_, models) => {
+                if let Some(picker) = self.state.model_picker.as_mut() {
+                    let models: Vec<ModelInfo> = models.into_iter().filter(ModelInfo::supports_chat).collect();
+                    picker.selected = models
+                        .iter()
+                        .position(|model| model.id() == self.client.model)
+                        .unwrap_or(0);
+                    self.state.status_message = format!("{} models available", models.len());
+                    picker.models = Some(models);
+                }
+            }
+            AppEvent::ModelsError(_, error) => {
+                self.state.model_picker = None;
+                self.state.status_message = format!("Could not list models: {}", error);
+            }
+        }
     }
 }
 
@@ -165,6 +1341,156 @@ impl Clone for GeminiClient {
             client: reqwest::Client::new(),
             api_key: self.api_key.clone(),
             base_url: self.base_url.clone(),
+            model: self.model.clone(),
+            generation_config: self.generation_config.clone(),
+            system_instruction: self.system_instruction.clone(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Parses a slash command argument, treating `default` as "unset".
+fn parse_param<T>(args: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if args.eq_ignore_ascii_case("default") {
+        return Ok(None);
+    }
+    args.parse()
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("{:?} is not a valid value ({})", args, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// An app whose conversations and prompt history live in a scratch
+    /// directory of its own.
+    fn app() -> App {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("gemini-chat-tui-app-{}-{}", std::process::id(), n));
+        let mut sessions = SessionStore::at(dir.join("sessions"));
+        let session = sessions.new_session();
+        let history = InputHistory::from_path(dir.join("history.jsonl"));
+        App::new(Config::default(), Keymap::default(), Theme::default(), sessions, session, history)
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    /// A finished exchange, saved to disk.
+    fn answered(app: &mut App) -> String {
+        app.state.add_message("question".to_string(), Role::User);
+        app.state.add_message("answer".to_string(), Role::Model);
+        app.save_session();
+        app.session.id.clone()
+    }
+
+    #[test]
+    fn test_switching_conversations_keeps_finished_turns() {
+        let mut app = app();
+        let first = answered(&mut app);
+
+        let mut other = app.sessions.new_session();
+        other.messages = app.state.messages.clone();
+        app.sessions.save(&mut other).unwrap();
+        app.open_session(&other.id);
+        assert_eq!(app.session.id, other.id);
+
+        let saved = app.sessions.load(&first).unwrap();
+        assert!(saved.messages.iter().all(|message| !message.cancelled));
+    }
+
+    #[test]
+    fn test_question_mark_types_into_an_empty_input() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('?'));
+        assert_eq!(app.state.input.text(), "?");
+        assert!(app.state.help.is_none());
+
+        press(&mut app, KeyCode::F(1));
+        assert!(app.state.help.is_some());
+    }
+
+    #[test]
+    fn test_clear_keeps_finished_turns() {
+        let mut app = app();
+        let first = answered(&mut app);
+
+        app.state.input.set_text("/clear");
+        app.run_action(Action::Send);
+        assert_ne!(app.session.id, first);
+        assert!(app.state.messages.is_empty());
+
+        let saved = app.sessions.load(&first).unwrap();
+        assert!(saved.messages.iter().all(|message| !message.cancelled));
+    }
+
+    #[test]
+    fn test_editing_in_visual_mode_leaves_it() {
+        let mut app = app();
+        app.state.vim = Some(Vim::default());
+        app.state.input.set_text("a\nb");
+
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('v'));
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.state.vim.as_ref().map(|vim| vim.mode), Some(Mode::Normal));
+
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.state.input.text(), "");
+    }
+
+    #[tokio::test]
+    async fn test_browsing_history_keeps_a_pasted_draft() {
+        let mut app = app();
+        app.remember_input("earlier prompt");
+        let code = (1..=120).map(|n| format!("line {}", n)).collect::<Vec<_>>().join("\n");
+        app.handle_paste(&code);
+
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.state.input.text(), "earlier prompt");
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.state.input.text(), "[pasted 120 lines]");
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.state.messages.last().map(|message| message.content.as_str()), Some(code.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_quitting_mid_reply_cancels_the_turn() {
+        let mut app = app();
+        app.state.input.set_text("unanswered");
+        app.run_action(Action::Send);
+        assert!(app.pending.is_some());
+
+        app.shut_down();
+        let saved = app.sessions.load(&app.session.id).unwrap();
+        assert!(saved.messages.last().is_some_and(|message| message.cancelled));
+        assert!(app.state.conversation().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_a_closed_pickers_models_are_dropped() {
+        let mut app = app();
+        app.open_model_picker();
+        let closed = app.state.model_picker.as_ref().unwrap().request_id;
+        press(&mut app, KeyCode::Esc);
+        assert!(app.state.model_picker.is_none());
+
+        app.open_model_picker();
+        app.handle_app_event(AppEvent::ModelsError(closed, "timed out".to_string()));
+        assert!(app.state.model_picker.is_some());
+        assert_eq!(app.state.status_message, "Fetching available models...");
+
+        let open = app.state.model_picker.as_ref().unwrap().request_id;
+        app.handle_app_event(AppEvent::ModelsError(open, "timed out".to_string()));
+        assert!(app.state.model_picker.is_none());
+    }
+}
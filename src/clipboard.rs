@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use crate::config::{create_private_file, get_data_dir};
+
+/// Terminals cap OSC 52 payloads; bigger copies go to a file instead.
+const MAX_OSC52_BYTES: usize = 100_000;
+
+/// Where copied text ended up.
+pub enum Copied {
+    Clipboard,
+    File(PathBuf),
+}
+
+/// Puts `text` on the system clipboard through the terminal, which also
+/// works over SSH, or saves it to `clipboard.txt` in the data directory
+/// when that is not possible.
+pub fn copy(text: &str) -> Result<Copied> {
+    let sequence = osc52(text, std::env::var_os("TMUX").is_some());
+    if sequence.len() <= MAX_OSC52_BYTES && supports_osc52() {
+        let mut stdout = std::io::stdout();
+        if stdout.write_all(sequence.as_bytes()).and_then(|_| stdout.flush()).is_ok() {
+            return Ok(Copied::Clipboard);
+        }
+    }
+
+    let dir = get_data_dir()?;
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join("clipboard.txt");
+    // The last copy is replaced, never written through
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("Failed to replace {}", path.display()));
+        }
+        _ => {}
+    }
+    create_private_file(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(Copied::File(path))
+}
+
+/// The Linux console and dumb terminals ignore clipboard requests.
+fn supports_osc52() -> bool {
+    !matches!(std::env::var("TERM").as_deref(), Ok("dumb") | Ok("linux"))
+}
+
+/// The escape sequence asking the terminal to set its clipboard; tmux only
+/// forwards it when wrapped in a passthrough with the escape doubled.
+fn osc52(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(osc52("hi", true), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
+}
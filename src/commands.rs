@@ -0,0 +1,307 @@
+/// What a slash command does; [`COMMANDS`] maps the typed names onto these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Clear,
+    Save,
+    Load,
+    Export,
+    Edit,
+    Model,
+    Models,
+    Temperature,
+    TopP,
+    TopK,
+    MaxTokens,
+    Stop,
+    Persona,
+    Theme,
+    Quit,
+}
+
+/// One entry in the slash command registry.
+#[derive(Debug)]
+pub struct CommandSpec {
+    pub command: Command,
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Argument syntax shown in hints, e.g. `[NAME]`.
+    pub usage: &'static str,
+    pub about: &'static str,
+}
+
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        command: Command::Help,
+        name: "help",
+        aliases: &["?"],
+        usage: "",
+        about: "Show keys and commands",
+    },
+    CommandSpec {
+        command: Command::Clear,
+        name: "clear",
+        aliases: &["new"],
+        usage: "",
+        about: "Start a new conversation; the current one stays saved",
+    },
+    CommandSpec {
+        command: Command::Save,
+        name: "save",
+        aliases: &[],
+        usage: "[TITLE]",
+        about: "Save the conversation now, optionally under a new title",
+    },
+    CommandSpec {
+        command: Command::Load,
+        name: "load",
+        aliases: &["open"],
+        usage: "[ID or TITLE]",
+        about: "Open a saved conversation, or browse them without an argument",
+    },
+    CommandSpec {
+        command: Command::Export,
+        name: "export",
+        aliases: &[],
+        usage: "[PATH]",
+        about: "Export the conversation as a Markdown file",
+    },
+    CommandSpec {
+        command: Command::Edit,
+        name: "edit",
+        aliases: &[],
+        usage: "",
+        about: "Write the message in $VISUAL / $EDITOR",
+    },
+    CommandSpec {
+        command: Command::Model,
+        name: "model",
+        aliases: &[],
+        usage: "[NAME]",
+        about: "Switch model (pro, flash, flash-lite or a full id); pick from a list without a name",
+    },
+    CommandSpec {
+        command: Command::Models,
+        name: "models",
+        aliases: &[],
+        usage: "",
+        about: "Pick from the models available to your key",
+    },
+    CommandSpec {
+        command: Command::Temperature,
+        name: "temp",
+        aliases: &["temperature"],
+        usage: "[VALUE|default]",
+        about: "Set the sampling temperature",
+    },
+    CommandSpec {
+        command: Command::TopP,
+        name: "top-p",
+        aliases: &[],
+        usage: "[VALUE|default]",
+        about: "Set nucleus sampling",
+    },
+    CommandSpec {
+        command: Command::TopK,
+        name: "top-k",
+        aliases: &[],
+        usage: "[VALUE|default]",
+        about: "Set top-k sampling",
+    },
+    CommandSpec {
+        command: Command::MaxTokens,
+        name: "max-tokens",
+        aliases: &[],
+        usage: "[COUNT|default]",
+        about: "Limit the length of replies",
+    },
+    CommandSpec {
+        command: Command::Stop,
+        name: "stop",
+        aliases: &[],
+        usage: "[SEQUENCE|default]",
+        about: "Add a stop sequence",
+    },
+    CommandSpec {
+        command: Command::Persona,
+        name: "persona",
+        aliases: &[],
+        usage: "[NAME|none]",
+        about: "Answer as a configured persona; list them without a name",
+    },
+    CommandSpec {
+        command: Command::Theme,
+        name: "theme",
+        aliases: &[],
+        usage: "[NAME]",
+        about: "Switch colors (dark, light, high-contrast, solarized, monochrome or a theme file); list them without a name",
+    },
+    CommandSpec {
+        command: Command::Quit,
+        name: "quit",
+        aliases: &["exit", "q"],
+        usage: "",
+        about: "Leave the app",
+    },
+];
+
+/// Whether the input line is a command rather than a message; `//` starts
+/// a message that begins with a slash.
+pub fn is_command(input: &str) -> bool {
+    input.starts_with('/') && !input.starts_with("//")
+}
+
+/// The message to send for a line that is not a command: `//text` sends
+/// `/text`, anything else goes as typed.
+pub fn unescape(line: &str) -> String {
+    match line.trim_start().strip_prefix("//") {
+        Some(rest) => format!("/{}", rest),
+        None => line.to_string(),
+    }
+}
+
+/// Looks a command up by its name or one of its aliases.
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS
+        .iter()
+        .find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+/// Splits `/name args` into the name and the trimmed arguments.
+pub fn split(line: &str) -> (&str, &str) {
+    let line = line.trim_start().trim_start_matches('/');
+    match line.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (line, ""),
+    }
+}
+
+/// Commands whose name starts with `prefix`.
+pub fn matching(prefix: &str) -> Vec<&'static CommandSpec> {
+    COMMANDS.iter().filter(|spec| spec.name.starts_with(prefix)).collect()
+}
+
+/// What to show above the input while a command is being typed: the usage
+/// of the command once it is known, otherwise the commands it could become.
+pub fn hint(input: &str) -> Option<String> {
+    if !is_command(input) || input.contains('\n') {
+        return None;
+    }
+    let (name, _) = split(input);
+    if let Some(spec) = find(name) {
+        return Some(match spec.usage {
+            "" => format!("/{} — {}", spec.name, spec.about),
+            usage => format!("/{} {} — {}", spec.name, usage, spec.about),
+        });
+    }
+
+    let candidates = matching(name);
+    if candidates.is_empty() {
+        return Some(format!("No command /{} — /help lists them all, // sends a message starting with /", name));
+    }
+    let names: Vec<String> = candidates.iter().map(|spec| format!("/{}", spec.name)).collect();
+    Some(names.join("  "))
+}
+
+/// Tab completion for the input line: command names first, then the
+/// `arguments` offered for that command. Returns the new input, if any.
+pub fn complete(input: &str, arguments: &[String]) -> Option<String> {
+    if !is_command(input) || input.contains('\n') {
+        return None;
+    }
+    let (name, args) = split(input);
+
+    if !input.contains(char::is_whitespace) {
+        let names: Vec<&str> = matching(name).iter().map(|spec| spec.name).collect();
+        return match names.as_slice() {
+            [] => None,
+            [only] => Some(format!("/{} ", only)),
+            _ => Some(format!("/{}", common_prefix(&names))).filter(|completed| completed != input),
+        };
+    }
+
+    let name = find(name)?.name;
+    let candidates: Vec<&str> = arguments
+        .iter()
+        .map(String::as_str)
+        .filter(|candidate| candidate.to_lowercase().starts_with(&args.to_lowercase()))
+        .collect();
+    let completed = match candidates.as_slice() {
+        [] => return None,
+        [only] => only.to_string(),
+        _ => common_prefix(&candidates).to_string(),
+    };
+    let completed = format!("/{} {}", name, completed);
+    (completed.trim_end() != input.trim_end()).then_some(completed)
+}
+
+fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let Some(first) = words.first() else {
+        return "";
+    };
+    let end = words.iter().fold(first.len(), |end, word| {
+        first
+            .char_indices()
+            .zip(word.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((at, c), _)| at + c.len_utf8())
+            .min(end)
+    });
+    &first[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_and_split() {
+        assert_eq!(find("new").map(|spec| spec.command), Some(Command::Clear));
+        assert_eq!(find("temperature").map(|spec| spec.command), Some(Command::Temperature));
+        assert!(find("nope").is_none());
+        assert_eq!(split("/model   pro "), ("model", "pro"));
+        assert_eq!(split("/help"), ("help", ""));
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(complete("/he", &[]).as_deref(), Some("/help "));
+        assert_eq!(complete("/mo", &[]).as_deref(), Some("/model"));
+        assert_eq!(complete("/model", &[]), None);
+        assert_eq!(complete("/zz", &[]), None);
+
+        let aliases = vec!["pro".to_string(), "flash".to_string(), "flash-lite".to_string()];
+        assert_eq!(complete("/model p", &aliases).as_deref(), Some("/model pro"));
+        assert_eq!(complete("/model fl", &aliases).as_deref(), Some("/model flash"));
+        assert_eq!(complete("/model flash", &aliases), None);
+        assert_eq!(complete("hello", &aliases), None);
+    }
+
+    #[test]
+    fn test_hint() {
+        assert_eq!(hint("/mo").as_deref(), Some("/model  /models"));
+        assert_eq!(hint("/q").as_deref(), Some("/quit — Leave the app"));
+        assert_eq!(
+            hint("/persona ").as_deref(),
+            Some("/persona [NAME|none] — Answer as a configured persona; list them without a name")
+        );
+        assert_eq!(hint("/help").as_deref(), Some("/help — Show keys and commands"));
+        assert_eq!(
+            hint("/xyz").as_deref(),
+            Some("No command /xyz — /help lists them all, // sends a message starting with /")
+        );
+        assert_eq!(hint("plain text"), None);
+    }
+
+    #[test]
+    fn test_double_slash_is_a_message() {
+        assert!(is_command("/help"));
+        assert!(!is_command("//etc/hosts is missing"));
+        assert!(!is_command("hello /help"));
+        assert_eq!(hint("//etc/hosts"), None);
+        assert_eq!(complete("//he", &[]), None);
+        assert_eq!(unescape("//etc/hosts is missing"), "/etc/hosts is missing");
+        assert_eq!(unescape("  indented // text"), "  indented // text");
+    }
+}
@@ -1,13 +1,66 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::{
+    gemini::{GenerationConfig, DEFAULT_MODEL},
+    keymap::KeyBindings,
+    theme::PRESETS,
+};
 use std::{
-    fs,
-    path::PathBuf,
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub api_key: String,
+    /// Show replies token by token instead of waiting for the whole answer.
+    pub stream: bool,
+    /// Gemini model id, e.g. `gemini-2.0-flash`.
+    pub model: String,
+    /// Default sampling parameters for every request.
+    pub generation: GenerationConfig,
+    /// Named system instructions, e.g. `"reviewer": "You are a Rust reviewer..."`.
+    pub personas: BTreeMap<String, String>,
+    /// Persona used when none is picked on the command line.
+    pub persona: Option<String>,
+    /// Edit the input box with Vim-style normal, insert and visual modes.
+    pub vim_mode: bool,
+    /// Keys for actions, replacing their defaults, e.g. `"send": "ctrl+j"` or
+    /// `"quit": ["ctrl+q", "ctrl+c"]`.
+    pub keymap: BTreeMap<String, KeyBindings>,
+    /// A built-in theme (`dark`, `light`, `high-contrast`, `solarized`,
+    /// `monochrome`) or the name of a file in `themes/` next to this one.
+    pub theme: String,
+    /// Colors replacing those of the theme, e.g. `"user": "#268bd2"`.
+    pub colors: Map<String, Value>,
+    /// Keep the title and the spinner still instead of redrawing them
+    /// every 100 ms.
+    pub reduced_motion: bool,
+    /// Leave out the title row and the margin around the screen.
+    pub compact: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            stream: true,
+            model: DEFAULT_MODEL.to_string(),
+            generation: GenerationConfig::default(),
+            personas: BTreeMap::new(),
+            persona: None,
+            vim_mode: false,
+            keymap: BTreeMap::new(),
+            theme: PRESETS[0].to_string(),
+            colors: Map::new(),
+            reduced_motion: false,
+            compact: false,
+        }
+    }
 }
 
 impl Config {
@@ -24,9 +77,7 @@ impl Config {
             Ok(config)
         } else {
             // Create default config
-            let config = Config {
-                api_key: String::new(),
-            };
+            let config = Config::default();
             config.save()?;
             Ok(config)
         }
@@ -49,6 +100,19 @@ impl Config {
         Ok(())
     }
 
+    /// Fails with the list of known personas if `name` is not one of them.
+    pub fn check_persona(&self, name: &str) -> Result<()> {
+        if !self.personas.contains_key(name) {
+            let known: Vec<&str> = self.personas.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "Unknown persona {:?} (available: {})",
+                name,
+                if known.is_empty() { "none configured".to_string() } else { known.join(", ") }
+            );
+        }
+        Ok(())
+    }
+
     pub fn set_api_key(&mut self, api_key: String) -> Result<()> {
         self.api_key = api_key;
         self.save()
@@ -56,11 +120,36 @@ impl Config {
 }
 
 fn get_config_path() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("config.json"))
+}
+
+/// The app's own config directory, e.g. `~/.config/gemini-chat-tui` on Linux.
+pub fn get_config_dir() -> Result<PathBuf> {
     let config_dir = dirs::config_dir()
         .or_else(|| dirs::home_dir().map(|h| h.join(".config")))
         .context("Unable to determine config directory")?;
     
-    Ok(config_dir.join("gemini-chat-tui").join("config.json"))
+    Ok(config_dir.join("gemini-chat-tui"))
+}
+
+/// Where saved conversations and other app data live, e.g.
+/// `~/.local/share/gemini-chat-tui` on Linux.
+pub fn get_data_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir()
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local").join("share")))
+        .context("Unable to determine data directory")?;
+    
+    Ok(data_dir.join("gemini-chat-tui"))
+}
+
+/// Creates `path` afresh, readable only by the user. An existing file, or a
+/// symlink left in its place, is an error rather than being written through.
+pub fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
 }
 
 pub fn prompt_for_api_key() -> Result<String> {
@@ -1,4 +1,7 @@
-use crate::ui::{ui, AppState};
+use crate::{
+    gemini::Role,
+    ui::{ui, AppState},
+};
 use anyhow::Result;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
@@ -25,11 +28,11 @@ pub fn run_demo() -> Result<()> {
     let mut state = AppState::default();
     
     // Add some demo messages to show the fixed bubbles with bold text
-    state.add_message("Hello Gemini! How are you today? Can you show me some **bold text** examples?".to_string(), true);
-    state.add_message("Hello! I'm doing **great**, thank you for asking! I'm here to help you with any questions or tasks you might have. Here are some **formatting examples**: This text has **bold parts** and normal parts mixed together. The weather has been lovely lately, and I've been enjoying our conversations. How has your day been going so far?".to_string(), false);
-    state.add_message("That's wonderful to hear! I've been working on a **TUI chat application** in **Rust** with very long messages that should wrap properly without distorting the bubble layout.".to_string(), true);
-    state.add_message("That sounds like an **exciting project**! Rust is an excellent choice for building **TUI applications**. The combination of **performance**, **memory safety**, and the rich ecosystem of crates like **ratatui** makes it perfect for creating responsive and beautiful terminal interfaces. Here's a very long word: supercalifragilisticexpialidocious which should be properly broken. Are you finding the development process enjoyable?".to_string(), false);
-    state.add_message("Yes, very much! The **bubble design** looks much better now with proper **text wrapping** and **bold formatting**!".to_string(), true);
+    state.add_message("Hello Gemini! How are you today? Can you show me some **bold text** examples?".to_string(), Role::User);
+    state.add_message("Hello! I'm doing **great**, thank you for asking! I'm here to help you with any questions or tasks you might have. Here are some **formatting examples**: This text has **bold parts** and normal parts mixed together. The weather has been lovely lately, and I've been enjoying our conversations. How has your day been going so far?".to_string(), Role::Model);
+    state.add_message("That's wonderful to hear! I've been working on a **TUI chat application** in **Rust** with very long messages that should wrap properly without distorting the bubble layout.".to_string(), Role::User);
+    state.add_message("That sounds like an **exciting project**! Rust is an excellent choice for building **TUI applications**. The combination of **performance**, **memory safety**, and the rich ecosystem of crates like **ratatui** makes it perfect for creating responsive and beautiful terminal interfaces. Here's a very long word: supercalifragilisticexpialidocious which should be properly broken. Are you finding the development process enjoyable?".to_string(), Role::Model);
+    state.add_message("Yes, very much! The **bubble design** looks much better now with proper **text wrapping** and **bold formatting**!".to_string(), Role::User);
 
     state.status_message = "Demo Mode - Press any key to continue, Ctrl+C to exit".to_string();
 
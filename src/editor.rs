@@ -0,0 +1,718 @@
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A place in the text: line index and byte offset into that line.
+pub type Position = (usize, usize);
+
+/// The multi-line text box behind "Your Message".
+///
+/// The cursor is kept as a line index plus a byte offset into that line
+/// that always sits on a grapheme boundary, so editing never splits a
+/// multi-byte character and movement treats `é` or `👍🏽` as one step.
+#[derive(Debug, Clone)]
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+    /// Large pastes shown as a one-line chip until the message is sent.
+    pastes: Vec<PastedBlock>,
+    /// The other end of a Vim visual selection; the cursor is one end.
+    anchor: Option<Position>,
+}
+
+/// One wrapped row of the input box: line index and byte range in the line.
+type Row = (usize, Range<usize>);
+
+/// Pastes with at least this many lines are collapsed into a chip.
+const PASTE_CHIP_MIN_LINES: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct PastedBlock {
+    /// The chip text standing in for the paste, e.g. `[pasted 120 lines]`.
+    pub placeholder: String,
+    pub content: String,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+            pastes: Vec::new(),
+            anchor: None,
+        }
+    }
+}
+
+impl TextArea {
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replaces the contents and puts the cursor at the very end.
+    pub fn set_text(&mut self, text: &str) {
+        *self = Self::default();
+        self.insert_str(text);
+    }
+
+    /// The text with every paste chip replaced by what was pasted; this is
+    /// what gets sent. A chip the user edited away is simply dropped.
+    ///
+    /// Chips are found in the text as typed, so pasted content is never
+    /// searched for chips. Where the same placeholder text also appears
+    /// typed, the last one is taken as the chip: a paste usually goes in
+    /// after the question about it.
+    pub fn expanded_text(&self) -> String {
+        let text = self.text();
+        let mut chips: Vec<(usize, &PastedBlock)> = self
+            .pastes
+            .iter()
+            .filter_map(|paste| text.rfind(&paste.placeholder).map(|start| (start, paste)))
+            .collect();
+        chips.sort_by_key(|(start, _)| *start);
+
+        let mut expanded = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (start, paste) in chips {
+            expanded.push_str(&text[copied..start]);
+            expanded.push_str(&paste.content);
+            copied = start + paste.placeholder.len();
+        }
+        expanded.push_str(&text[copied..]);
+        expanded
+    }
+
+    /// The chip placeholders, for highlighting in the input box.
+    pub fn paste_chips(&self) -> impl Iterator<Item = &str> {
+        self.pastes.iter().map(|paste| paste.placeholder.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.len() == 1 && self.lines[0].is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_on_first_line(&self) -> bool {
+        self.row == 0
+    }
+
+    pub fn is_on_last_line(&self) -> bool {
+        self.row + 1 == self.lines.len()
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        if c == '\n' {
+            self.insert_newline();
+        } else {
+            self.lines[self.row].insert(self.col, c);
+            self.col += c.len_utf8();
+        }
+    }
+
+    /// Inserts text that may span several lines.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let mut pieces = text.split('\n');
+
+        if let Some(first) = pieces.next() {
+            self.lines[self.row].insert_str(self.col, first);
+            self.col += first.len();
+        }
+        for piece in pieces {
+            self.insert_newline();
+            self.lines[self.row].insert_str(self.col, piece);
+            self.col += piece.len();
+        }
+    }
+
+    /// Inserts a bracketed paste in one go. Big pastes become a
+    /// `[pasted N lines]` chip so the input box stays readable.
+    pub fn paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let line_count = text.trim_end_matches('\n').lines().count();
+        if line_count < PASTE_CHIP_MIN_LINES {
+            self.insert_str(&text);
+            return;
+        }
+
+        let placeholder = if self.pastes.is_empty() {
+            format!("[pasted {} lines]", line_count)
+        } else {
+            format!("[pasted {} lines #{}]", line_count, self.pastes.len() + 1)
+        };
+        self.insert_str(&placeholder);
+        self.pastes.push(PastedBlock {
+            placeholder,
+            content: text,
+        });
+    }
+
+    pub fn insert_newline(&mut self) {
+        let rest = self.lines[self.row].split_off(self.col);
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    /// Deletes the grapheme before the cursor, joining lines at column 0.
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            let start = prev_boundary(&self.lines[self.row], self.col);
+            self.lines[self.row].replace_range(start..self.col, "");
+            self.col = start;
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Deletes the grapheme under the cursor, joining lines at the end.
+    pub fn delete(&mut self) {
+        let line = &self.lines[self.row];
+        if self.col < line.len() {
+            let end = next_boundary(line, self.col);
+            self.lines[self.row].replace_range(self.col..end, "");
+        } else if !self.is_on_last_line() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    /// Ctrl+W: deletes back to the start of the previous word, like a shell.
+    pub fn delete_word_before(&mut self) {
+        if self.col == 0 {
+            self.backspace();
+            return;
+        }
+
+        let line = &self.lines[self.row];
+        let graphemes: Vec<(usize, &str)> = line[..self.col].grapheme_indices(true).collect();
+        let mut i = graphemes.len();
+        while i > 0 && is_blank(graphemes[i - 1].1) {
+            i -= 1;
+        }
+        while i > 0 && !is_blank(graphemes[i - 1].1) {
+            i -= 1;
+        }
+
+        let start = graphemes.get(i).map_or(0, |(index, _)| *index);
+        self.lines[self.row].replace_range(start..self.col, "");
+        self.col = start;
+    }
+
+    /// Ctrl+U: deletes from the start of the line to the cursor.
+    pub fn delete_to_line_start(&mut self) {
+        if self.col == 0 {
+            self.backspace();
+        } else {
+            self.lines[self.row].replace_range(..self.col, "");
+            self.col = 0;
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col = prev_boundary(&self.lines[self.row], self.col);
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.col < self.lines[self.row].len() {
+            self.col = next_boundary(&self.lines[self.row], self.col);
+        } else if !self.is_on_last_line() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    /// Moves up one line, keeping the on-screen column where possible.
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            let column = self.lines[self.row][..self.col].width();
+            self.row -= 1;
+            self.col = byte_at_column(&self.lines[self.row], column);
+        }
+    }
+
+    /// Moves down one line, keeping the on-screen column where possible.
+    pub fn move_down(&mut self) {
+        if !self.is_on_last_line() {
+            let column = self.lines[self.row][..self.col].width();
+            self.row += 1;
+            self.col = byte_at_column(&self.lines[self.row], column);
+        }
+    }
+
+    pub fn move_home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.col = self.lines[self.row].len();
+    }
+
+    /// Ctrl+Left: jumps to the start of the current or previous word.
+    pub fn move_word_left(&mut self) {
+        if self.col == 0 {
+            self.move_left();
+            return;
+        }
+
+        let line = &self.lines[self.row];
+        let graphemes: Vec<(usize, &str)> = line[..self.col].grapheme_indices(true).collect();
+        let mut i = graphemes.len();
+        while i > 0 && !is_word(graphemes[i - 1].1) {
+            i -= 1;
+        }
+        while i > 0 && is_word(graphemes[i - 1].1) {
+            i -= 1;
+        }
+        self.col = graphemes.get(i).map_or(0, |(index, _)| *index);
+    }
+
+    /// Ctrl+Right: jumps past the end of the current or next word.
+    pub fn move_word_right(&mut self) {
+        let line = &self.lines[self.row];
+        if self.col == line.len() {
+            self.move_right();
+            return;
+        }
+
+        let mut graphemes = line[self.col..].grapheme_indices(true).peekable();
+        while graphemes.next_if(|(_, g)| !is_word(g)).is_some() {}
+        while graphemes.next_if(|(_, g)| is_word(g)).is_some() {}
+        self.col = graphemes.peek().map_or(line.len(), |(index, _)| self.col + index);
+    }
+
+    pub fn cursor(&self) -> Position {
+        (self.row, self.col)
+    }
+
+    /// Moves the cursor, clamped to the text and to a grapheme boundary.
+    pub fn set_cursor(&mut self, position: Position) {
+        (self.row, self.col) = self.clamp(position);
+    }
+
+    /// The nearest position inside the text, at the start of the grapheme
+    /// `position` falls in.
+    fn clamp(&self, (row, col): Position) -> Position {
+        let row = row.min(self.lines.len() - 1);
+        let line = &self.lines[row];
+        let col = if col >= line.len() {
+            line.len()
+        } else {
+            line.grapheme_indices(true).map(|(index, _)| index).take_while(|index| *index <= col).last().unwrap_or(0)
+        };
+        (row, col)
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn line(&self, row: usize) -> &str {
+        &self.lines[row]
+    }
+
+    /// The position one grapheme after `position`, stepping onto the next
+    /// line from the end of one.
+    pub fn after(&self, position: Position) -> Position {
+        let (row, col) = self.clamp(position);
+        let line = &self.lines[row];
+        if col < line.len() {
+            (row, next_boundary(line, col))
+        } else if row + 1 < self.lines.len() {
+            (row + 1, 0)
+        } else {
+            (row, col)
+        }
+    }
+
+    /// The text between two positions, `end` exclusive.
+    pub fn text_range(&self, start: Position, end: Position) -> String {
+        let (start, end) = (self.clamp(start), self.clamp(end));
+        if start.0 == end.0 {
+            return self.lines[start.0][start.1..end.1].to_string();
+        }
+        let mut text = self.lines[start.0][start.1..].to_string();
+        for line in &self.lines[start.0 + 1..end.0] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&self.lines[end.0][..end.1]);
+        text
+    }
+
+    /// Removes the text between two positions, `end` exclusive, leaving the
+    /// cursor at `start`, and returns what was removed.
+    pub fn delete_range(&mut self, start: Position, end: Position) -> String {
+        let (start, end) = (self.clamp(start), self.clamp(end));
+        let removed = self.text_range(start, end);
+        let tail = self.lines[end.0][end.1..].to_string();
+        self.lines.drain(start.0 + 1..=end.0);
+        self.lines[start.0].replace_range(start.1.., &tail);
+        self.row = start.0;
+        self.col = start.1;
+        removed
+    }
+
+    /// Removes `count` whole lines from the cursor's line down and returns
+    /// them, each ending in a newline.
+    pub fn delete_lines(&mut self, count: usize) -> String {
+        let end = (self.row + count.max(1)).min(self.lines.len());
+        let removed: String = self.lines.drain(self.row..end).map(|line| line + "\n").collect();
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.row = self.row.min(self.lines.len() - 1);
+        self.move_first_non_blank();
+        removed
+    }
+
+    /// Vim's `^`: the first character on the line that is not a space.
+    pub fn move_first_non_blank(&mut self) {
+        let line = &self.lines[self.row];
+        self.col = line.find(|c: char| !c.is_whitespace()).unwrap_or(line.len());
+    }
+
+    /// Vim's `w`: the start of the next word, where runs of letters and of
+    /// punctuation are words of their own.
+    pub fn move_word_forward(&mut self) {
+        let line = &self.lines[self.row];
+        if self.col == line.len() {
+            if self.is_on_last_line() {
+                return;
+            }
+            self.row += 1;
+            self.col = 0;
+        } else {
+            let class = grapheme_class(line[self.col..].graphemes(true).next().unwrap_or(" "));
+            let mut graphemes = line[self.col..].grapheme_indices(true).peekable();
+            if class != Class::Blank {
+                while graphemes.next_if(|(_, g)| grapheme_class(g) == class).is_some() {}
+            }
+            self.col = graphemes.peek().map_or(line.len(), |(index, _)| self.col + index);
+            if self.col == line.len() && !self.is_on_last_line() {
+                self.row += 1;
+                self.col = 0;
+            }
+        }
+        let line = &self.lines[self.row];
+        self.col += line[self.col..].find(|c: char| !c.is_whitespace()).unwrap_or(line.len() - self.col);
+    }
+
+    /// Byte range of the word under the cursor, like Vim's `iw`; with
+    /// `around` the spaces after it (or else before it) are included too.
+    pub fn word_at_cursor(&self, around: bool) -> Range<usize> {
+        let line = &self.lines[self.row];
+        let graphemes: Vec<(usize, &str)> = line.grapheme_indices(true).collect();
+        let Some(at) = graphemes.iter().position(|(index, _)| *index >= self.col).or(graphemes.len().checked_sub(1)) else {
+            return 0..0;
+        };
+        let class = grapheme_class(graphemes[at].1);
+        let same = |i: &usize| grapheme_class(graphemes[*i].1) == class;
+        let mut start = (0..at).rev().take_while(same).last().unwrap_or(at);
+        let mut end = (at..graphemes.len()).take_while(same).last().unwrap_or(at) + 1;
+        if around {
+            let blank = |i: &usize| grapheme_class(graphemes[*i].1) == Class::Blank;
+            let after = (end..graphemes.len()).take_while(blank).count();
+            if after > 0 {
+                end += after;
+            } else {
+                start -= (0..start).rev().take_while(blank).count();
+            }
+        }
+        let byte = |i: usize| graphemes.get(i).map_or(line.len(), |(index, _)| *index);
+        byte(start)..byte(end)
+    }
+
+    /// In Vim's normal mode the cursor sits on a character, not after the
+    /// last one.
+    pub fn clamp_to_last_char(&mut self) {
+        let line = &self.lines[self.row];
+        if self.col > 0 && self.col >= line.len() {
+            self.col = prev_boundary(line, line.len());
+        }
+    }
+
+    /// Starts or ends a visual selection at the cursor.
+    pub fn set_anchor(&mut self, anchor: Option<Position>) {
+        self.anchor = anchor;
+    }
+
+    /// The visual selection in text order, `end` exclusive, so the
+    /// character under the cursor is always included.
+    pub fn selection(&self) -> Option<(Position, Position)> {
+        let anchor = self.clamp(self.anchor?);
+        let cursor = self.cursor();
+        let (start, last) = if anchor <= cursor { (anchor, cursor) } else { (cursor, anchor) };
+        Some((start, self.after(last)))
+    }
+
+    /// Breaks the text into rows at most `width` columns wide and returns
+    /// them along with the cursor's (column, row) among those rows.
+    pub fn wrapped(&self, width: usize) -> (Vec<String>, (usize, usize)) {
+        let (rows, cursor) = self.layout(width);
+        let rows = rows.into_iter().map(|(line, range)| self.lines[line][range].to_string()).collect();
+        (rows, cursor)
+    }
+
+    /// For each row of [`TextArea::wrapped`], the part of it inside the
+    /// visual selection, as a byte range of the row.
+    pub fn selected_in_rows(&self, width: usize) -> Vec<Option<Range<usize>>> {
+        let (rows, _) = self.layout(width);
+        let Some((start, end)) = self.selection() else {
+            return vec![None; rows.len()];
+        };
+        rows.into_iter()
+            .map(|(line, range)| {
+                if line < start.0 || line > end.0 {
+                    return None;
+                }
+                let from = if line == start.0 { start.1 } else { 0 };
+                let to = if line == end.0 { end.1 } else { self.lines[line].len() };
+                let from = from.max(range.start);
+                let to = to.min(range.end);
+                (from < to).then(|| from - range.start..to - range.start)
+            })
+            .collect()
+    }
+
+    /// The wrapped rows plus the cursor's (column, row).
+    fn layout(&self, width: usize) -> (Vec<Row>, (usize, usize)) {
+        let width = width.max(1);
+        let mut rows = Vec::new();
+        let mut cursor = (0, 0);
+
+        for (line_index, line) in self.lines.iter().enumerate() {
+            let mut row_start = 0;
+            let mut row_width = 0;
+
+            for (byte, grapheme) in line.grapheme_indices(true) {
+                let grapheme_width = grapheme.width();
+                if row_width + grapheme_width > width && byte > row_start {
+                    rows.push((line_index, row_start..byte));
+                    row_start = byte;
+                    row_width = 0;
+                }
+                if line_index == self.row && byte == self.col {
+                    cursor = (row_width, rows.len());
+                }
+                row_width += grapheme_width;
+            }
+
+            if line_index == self.row && self.col == line.len() {
+                if row_width >= width {
+                    // The cursor would sit on the border; give it a fresh row
+                    rows.push((line_index, row_start..line.len()));
+                    row_start = line.len();
+                    row_width = 0;
+                }
+                cursor = (row_width, rows.len());
+            }
+            rows.push((line_index, row_start..line.len()));
+        }
+
+        (rows, cursor)
+    }
+}
+
+fn prev_boundary(line: &str, col: usize) -> usize {
+    line[..col].grapheme_indices(true).next_back().map_or(0, |(index, _)| index)
+}
+
+fn next_boundary(line: &str, col: usize) -> usize {
+    line[col..].graphemes(true).next().map_or(col, |g| col + g.len())
+}
+
+/// The byte offset of the grapheme at display column `column`, or the end
+/// of the line if it is shorter.
+fn byte_at_column(line: &str, column: usize) -> usize {
+    let mut used = 0;
+    for (index, grapheme) in line.grapheme_indices(true) {
+        let width = grapheme.width();
+        if used + width > column {
+            return index;
+        }
+        used += width;
+    }
+    line.len()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+fn grapheme_class(grapheme: &str) -> Class {
+    if is_blank(grapheme) {
+        Class::Blank
+    } else if is_word(grapheme) {
+        Class::Word
+    } else {
+        Class::Punctuation
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().any(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn is_blank(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text: &str) -> TextArea {
+        let mut input = TextArea::default();
+        for c in text.chars() {
+            input.insert_char(c);
+        }
+        input
+    }
+
+    #[test]
+    fn test_editing_non_ascii_text() {
+        let mut input = typed("héllo wörld");
+        input.move_left();
+        input.move_left();
+        input.backspace();
+        assert_eq!(input.text(), "héllo wöld");
+
+        let (_, cursor) = input.wrapped(80);
+        assert_eq!(cursor, (8, 0));
+    }
+
+    #[test]
+    fn test_graphemes_move_as_one() {
+        let mut input = typed("a👍🏽b");
+        input.move_left();
+        input.move_left();
+        assert_eq!(input.wrapped(80).1, (1, 0));
+        input.delete();
+        assert_eq!(input.text(), "ab");
+    }
+
+    #[test]
+    fn test_newlines_and_vertical_movement() {
+        let mut input = typed("first line\nab\nthird line");
+        assert_eq!(input.text().lines().count(), 3);
+
+        input.move_up();
+        assert_eq!(input.wrapped(80).1, (2, 1));
+        input.move_up();
+        assert_eq!(input.wrapped(80).1, (2, 0));
+        input.move_end();
+        input.delete();
+        assert_eq!(input.text(), "first lineab\nthird line");
+    }
+
+    #[test]
+    fn test_word_motions_and_deletions() {
+        let mut input = typed("let answer = compute(42);");
+        input.move_word_left();
+        input.move_word_left();
+        input.move_word_left();
+        input.move_word_right();
+        assert_eq!(input.wrapped(80).1, (10, 0));
+
+        input.move_end();
+        input.delete_word_before();
+        assert_eq!(input.text(), "let answer = ");
+        input.delete_to_line_start();
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_wrapping_places_cursor_on_continuation_row() {
+        let mut input = typed("abcdefgh");
+        let (rows, cursor) = input.wrapped(4);
+        assert_eq!(rows, vec!["abcd", "efgh", ""]);
+        assert_eq!(cursor, (0, 2));
+
+        input.move_left();
+        assert_eq!(input.wrapped(4).1, (3, 1));
+    }
+
+    #[test]
+    fn test_small_paste_is_inserted_inline() {
+        let mut input = typed("see: ");
+        input.paste("fn main() {\r\n}\r\n");
+        assert_eq!(input.text(), "see: fn main() {\n}\n");
+        assert_eq!(input.paste_chips().count(), 0);
+    }
+
+    #[test]
+    fn test_large_paste_becomes_chip_and_expands() {
+        let code: String = (1..=120).map(|i| format!("line {}\n", i)).collect();
+        let mut input = typed("Explain ");
+        input.paste(&code);
+        input.insert_char(' ');
+        input.insert_char('?');
+
+        assert_eq!(input.text(), "Explain [pasted 120 lines] ?");
+        assert_eq!(input.expanded_text(), format!("Explain {} ?", code));
+
+        input.paste(&code);
+        assert!(input.text().ends_with("[pasted 120 lines #2]"));
+    }
+
+    #[test]
+    fn test_typed_placeholder_is_not_expanded() {
+        let code: String = (1..=12).map(|i| format!("line {}\n", i)).collect();
+        let mut input = typed("what does [pasted 12 lines] mean in ");
+        input.paste(&code);
+        assert_eq!(input.expanded_text(), format!("what does [pasted 12 lines] mean in {}", code));
+
+        // Pasted text that looks like a later chip stays as it was
+        let mut input = typed("");
+        input.paste(&format!("[pasted 12 lines #2]\n{}", code));
+        input.paste(&code);
+        assert_eq!(input.expanded_text(), format!("[pasted 12 lines #2]\n{}{}", code, code));
+    }
+
+    #[test]
+    fn test_selection_follows_wrapping() {
+        let mut input = typed("abcdefgh\nxy");
+        input.set_cursor((0, 2));
+        input.set_anchor(Some(input.cursor()));
+        input.set_cursor((0, 5));
+        assert_eq!(input.selection(), Some(((0, 2), (0, 6))));
+        assert_eq!(input.selected_in_rows(4), vec![Some(2..4), Some(0..2), None]);
+
+        // Ranges across lines include the newline
+        assert_eq!(input.text_range((0, 6), (1, 1)), "gh\nx");
+        assert_eq!(input.delete_range((0, 6), (1, 1)), "gh\nx");
+        assert_eq!(input.text(), "abcdefy");
+    }
+
+    #[test]
+    fn test_stale_positions_are_clamped() {
+        let mut input = typed("a\nb");
+        input.set_anchor(Some((1, 0)));
+        input.backspace();
+        input.backspace();
+        input.backspace();
+        assert_eq!(input.text(), "");
+
+        assert_eq!(input.selection(), Some(((0, 0), (0, 0))));
+        assert_eq!(input.text_range((0, 0), (1, 1)), "");
+        assert_eq!(input.delete_range((0, 0), (1, 1)), "");
+    }
+}
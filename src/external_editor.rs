@@ -0,0 +1,76 @@
+use anyhow::{bail, Context, Result};
+use std::{fs, io::Write, path::Path, process::Command};
+
+use crate::config::{create_private_file, get_data_dir};
+
+/// Opens `text` in the user's editor and returns it as saved. The terminal
+/// must already be handed back to the shell.
+pub fn edit(text: &str) -> Result<String> {
+    let dir = get_data_dir()?;
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    edit_with(&editor_command(), text, &dir)
+}
+
+/// Edits `text` in a draft file made in `dir`, readable only by the user
+/// and removed once the editor exits.
+fn edit_with(editor: &str, text: &str, dir: &Path) -> Result<String> {
+    let mut words = editor.split_whitespace();
+    let program = words.next().context("$VISUAL / $EDITOR is empty")?;
+
+    // `.md` so editors highlight the prompt as Markdown
+    let path = dir.join(format!("draft-{}.md", std::process::id()));
+    // Left behind if this process id once crashed mid-edit
+    let _ = fs::remove_file(&path);
+    if let Err(e) = create_private_file(&path).and_then(|mut file| file.write_all(text.as_bytes())) {
+        let _ = fs::remove_file(&path);
+        return Err(e).with_context(|| format!("Failed to write {}", path.display()));
+    }
+
+    let status = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Failed to start {}", editor));
+    let edited = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()));
+    let _ = fs::remove_file(&path);
+
+    let status = status?;
+    if !status.success() {
+        bail!("{} exited with {}", editor, status);
+    }
+    // Editors add a final newline the prompt never had
+    Ok(edited?.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// `$VISUAL`, then `$EDITOR`, then the platform's stock editor. The value
+/// may carry arguments, e.g. `code --wait`.
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_round_trip() {
+        let dir = std::env::temp_dir().join(format!("gemini-chat-tui-editor-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let edited = edit_with("sed -i s/draft/final/", "a draft prompt\n\nsecond paragraph", &dir).unwrap();
+        assert_eq!(edited, "a final prompt\n\nsecond paragraph");
+
+        let error = edit_with("false", "text", &dir).unwrap_err().to_string();
+        assert!(error.starts_with("false exited with"), "{}", error);
+        assert!(edit_with("no-such-editor-here", "text", &dir).is_err());
+
+        // Every draft is cleaned up
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+}
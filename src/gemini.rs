@@ -2,45 +2,268 @@ use anyhow::Result;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiRequest {
     contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<SystemInstruction>,
+    #[serde(skip_serializing_if = "GenerationConfig::is_empty")]
+    generation_config: GenerationConfig,
 }
 
-#[derive(Debug, Serialize)]
-struct Content {
+#[derive(Debug, Clone, Serialize)]
+struct SystemInstruction {
     parts: Vec<Part>,
 }
 
-#[derive(Debug, Serialize)]
+/// Sampling parameters sent as `generationConfig`. Unset fields are left
+/// out of the request so the model's own defaults apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+}
+
+impl GenerationConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Rejects values the API would refuse anyway, with a readable message.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                anyhow::bail!("temperature must be between 0.0 and 2.0, got {}", temperature);
+            }
+        }
+        if let Some(top_p) = self.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                anyhow::bail!("topP must be between 0.0 and 1.0, got {}", top_p);
+            }
+        }
+        if self.top_k == Some(0) {
+            anyhow::bail!("topK must be at least 1");
+        }
+        if self.max_output_tokens == Some(0) {
+            anyhow::bail!("maxOutputTokens must be at least 1");
+        }
+        if self.stop_sequences.len() > 5 {
+            anyhow::bail!("at most 5 stop sequences are allowed");
+        }
+        Ok(())
+    }
+
+    /// One-line description for the status bar.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(temperature) = self.temperature {
+            parts.push(format!("temp {}", temperature));
+        }
+        if let Some(top_p) = self.top_p {
+            parts.push(format!("top-p {}", top_p));
+        }
+        if let Some(top_k) = self.top_k {
+            parts.push(format!("top-k {}", top_k));
+        }
+        if let Some(max_output_tokens) = self.max_output_tokens {
+            parts.push(format!("max {}", max_output_tokens));
+        }
+        if !self.stop_sequences.is_empty() {
+            parts.push(format!("stop {:?}", self.stop_sequences));
+        }
+
+        if parts.is_empty() {
+            "default sampling".to_string()
+        } else {
+            parts.join(" · ")
+        }
+    }
+}
+
+/// Who authored a turn in the conversation, as understood by the Gemini API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Model,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Content {
+    role: Role,
+    parts: Vec<Part>,
+}
+
+impl Content {
+    pub fn new(role: Role, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            parts: vec![Part { text: text.into() }],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 struct Part {
     text: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct GeminiResponse {
+    // Streamed chunks may carry only usage metadata and no candidates.
+    #[serde(default)]
     candidates: Vec<Candidate>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Candidate {
+    #[serde(default)]
     content: ResponseContent,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct ResponseContent {
+    #[serde(default)]
     parts: Vec<ResponsePart>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ResponsePart {
+    #[serde(default)]
     text: String,
 }
 
+impl GeminiResponse {
+    /// All text parts of the first candidate, joined together.
+    fn text(&self) -> Option<String> {
+        let candidate = self.candidates.first()?;
+        Some(
+            candidate
+                .content
+                .parts
+                .iter()
+                .map(|part| part.text.as_str())
+                .collect(),
+        )
+    }
+}
+
+/// A model as described by the `models.list` endpoint.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    /// Resource name, e.g. `models/gemini-2.0-flash`.
+    pub name: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub input_token_limit: u64,
+    #[serde(default)]
+    pub output_token_limit: u64,
+    #[serde(default)]
+    pub supported_generation_methods: Vec<String>,
+}
+
+impl ModelInfo {
+    /// The bare model id used in request URLs.
+    pub fn id(&self) -> &str {
+        self.name.strip_prefix("models/").unwrap_or(&self.name)
+    }
+
+    /// Whether the model can be used for chatting at all.
+    pub fn supports_chat(&self) -> bool {
+        self.supported_generation_methods
+            .iter()
+            .any(|method| method == "generateContent")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListModelsResponse {
+    #[serde(default)]
+    models: Vec<ModelInfo>,
+    next_page_token: Option<String>,
+}
+
+/// Splits a `text/event-stream` body into the payloads of its `data:` lines.
+/// Network chunks can end anywhere, so incomplete events stay buffered
+/// until the rest arrives.
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        while let Some((end, separator_len)) = find_event_end(&self.buffer) {
+            let raw: Vec<u8> = self.buffer.drain(..end + separator_len).collect();
+            let event = String::from_utf8_lossy(&raw[..end]);
+
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+
+            if !data.is_empty() {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+}
+
+fn find_event_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    let lf = buffer.windows(2).position(|w| w == b"\n\n").map(|i| (i, 2));
+    let crlf = buffer.windows(4).position(|w| w == b"\r\n\r\n").map(|i| (i, 4));
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(if a.0 < b.0 { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+pub const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+
+/// Short names accepted by `--model` and `/model`.
+pub const MODEL_ALIASES: &[(&str, &str)] = &[
+    ("pro", "gemini-2.5-pro"),
+    ("flash", "gemini-2.5-flash"),
+    ("flash-lite", "gemini-2.5-flash-lite"),
+];
+
+/// Turns an alias or a `models/...` resource name into a bare model id.
+pub fn resolve_model(name: &str) -> String {
+    let name = name.trim();
+    let name = name.strip_prefix("models/").unwrap_or(name);
+    MODEL_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map(|(_, model)| model.to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
 pub struct GeminiClient {
     pub client: Client,
     pub api_key: String,
     pub base_url: String,
+    pub model: String,
+    pub generation_config: GenerationConfig,
+    /// Sent as `systemInstruction` to pin the model's behavior (a persona).
+    pub system_instruction: Option<String>,
 }
 
 impl GeminiClient {
@@ -48,22 +271,81 @@ impl GeminiClient {
         Self {
             client: Client::new(),
             api_key,
-            base_url: "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent".to_string(),
+            base_url: "https://generativelanguage.googleapis.com/v1beta".to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            generation_config: GenerationConfig::default(),
+            system_instruction: None,
         }
     }
 
-    pub async fn send_message(&self, message: &str) -> Result<String> {
-        let request = GeminiRequest {
-            contents: vec![Content {
-                parts: vec![Part {
-                    text: message.to_string(),
-                }],
-            }],
-        };
+    pub fn generate_url(&self) -> String {
+        format!("{}/models/{}:generateContent", self.base_url, self.model)
+    }
+
+    pub fn stream_url(&self) -> String {
+        format!("{}/models/{}:streamGenerateContent?alt=sse", self.base_url, self.model)
+    }
+
+    fn build_request(&self, history: &[Content]) -> Result<GeminiRequest> {
+        if history.is_empty() {
+            anyhow::bail!("Cannot send an empty conversation");
+        }
+
+        Ok(GeminiRequest {
+            contents: history.to_vec(),
+            system_instruction: self
+                .system_instruction
+                .as_ref()
+                .filter(|text| !text.trim().is_empty())
+                .map(|text| SystemInstruction {
+                    parts: vec![Part { text: text.clone() }],
+                }),
+            generation_config: self.generation_config.clone(),
+        })
+    }
+
+    /// Fetches every model available to this API key, following pagination.
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut request = self
+                .client
+                .get(format!("{}/models", self.base_url))
+                .header("X-goog-api-key", &self.api_key)
+                .query(&[("pageSize", "1000")]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                anyhow::bail!("API request failed: {}", error_text);
+            }
+
+            let page: ListModelsResponse = response.json().await?;
+            models.extend(page.models);
+
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+
+        Ok(models)
+    }
+
+    /// Sends the whole conversation so far and returns the model's reply.
+    /// `history` must be in chronological order and end with the user turn
+    /// that should be answered.
+    pub async fn send_message(&self, history: &[Content]) -> Result<String> {
+        let request = self.build_request(history)?;
 
         let response = self
             .client
-            .post(&self.base_url)
+            .post(self.generate_url())
             .header("Content-Type", "application/json")
             .header("X-goog-api-key", &self.api_key)
             .json(&request)
@@ -77,15 +359,53 @@ impl GeminiClient {
 
         let gemini_response: GeminiResponse = response.json().await?;
         
-        if let Some(candidate) = gemini_response.candidates.first() {
-            if let Some(part) = candidate.content.parts.first() {
-                Ok(part.text.clone())
-            } else {
-                anyhow::bail!("No response parts found");
+        match gemini_response.text() {
+            Some(text) if !text.is_empty() => Ok(text),
+            Some(_) => anyhow::bail!("No response parts found"),
+            None => anyhow::bail!("No candidates found in response"),
+        }
+    }
+
+    /// Like [`send_message`](Self::send_message), but calls `on_chunk` with
+    /// each piece of text as soon as Gemini streams it. Returns the full reply.
+    pub async fn stream_message<F>(&self, history: &[Content], mut on_chunk: F) -> Result<String>
+    where
+        F: FnMut(String),
+    {
+        let request = self.build_request(history)?;
+
+        let mut response = self
+            .client
+            .post(self.stream_url())
+            .header("Content-Type", "application/json")
+            .header("X-goog-api-key", &self.api_key)
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("API request failed: {}", error_text);
+        }
+
+        let mut parser = SseParser::default();
+        let mut full_text = String::new();
+
+        while let Some(bytes) = response.chunk().await? {
+            for data in parser.push(&bytes) {
+                let chunk: GeminiResponse = serde_json::from_str(&data)?;
+                if let Some(text) = chunk.text().filter(|text| !text.is_empty()) {
+                    full_text.push_str(&text);
+                    on_chunk(text);
+                }
             }
-        } else {
+        }
+
+        if full_text.is_empty() {
             anyhow::bail!("No candidates found in response");
         }
+
+        Ok(full_text)
     }
 }
 
@@ -98,19 +418,29 @@ mod tests {
         let client = GeminiClient::new("test_api_key".to_string());
         assert_eq!(client.api_key, "test_api_key");
         assert_eq!(
-            client.base_url,
+            client.generate_url(),
             "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent"
         );
     }
 
+    #[test]
+    fn test_model_selection_changes_endpoints() {
+        let mut client = GeminiClient::new("test_api_key".to_string());
+        client.model = resolve_model("flash-lite");
+        assert_eq!(
+            client.stream_url(),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash-lite:streamGenerateContent?alt=sse"
+        );
+
+        assert_eq!(resolve_model("models/gemini-1.5-pro"), "gemini-1.5-pro");
+        assert_eq!(resolve_model(" PRO "), "gemini-2.5-pro");
+    }
+
     #[test]
     fn test_request_serialization() {
         let request = GeminiRequest {
-            contents: vec![Content {
-                parts: vec![Part {
-                    text: "Hello, world!".to_string(),
-                }],
-            }],
+            contents: vec![Content::new(Role::User, "Hello, world!")],
+            ..Default::default()
         };
         
         let json = serde_json::to_string(&request).unwrap();
@@ -119,4 +449,135 @@ mod tests {
         assert!(json.contains("parts"));
         assert!(json.contains("text"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_history_serialization_keeps_roles_in_order() {
+        let request = GeminiRequest {
+            contents: vec![
+                Content::new(Role::User, "My name is Ada."),
+                Content::new(Role::Model, "Nice to meet you, Ada!"),
+                Content::new(Role::User, "What is my name?"),
+            ],
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        let contents = json["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[0]["role"], "user");
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[2]["role"], "user");
+        assert_eq!(contents[2]["parts"][0]["text"], "What is my name?");
+    }
+
+    #[test]
+    fn test_sse_parser_handles_split_events() {
+        let mut parser = SseParser::default();
+
+        assert!(parser.push(b"data: {\"a\":").is_empty());
+        let events = parser.push(b"1}\r\n\r\ndata: {\"b\":2}\n\n");
+        assert_eq!(events, vec!["{\"a\":1}", "{\"b\":2}"]);
+        assert!(parser.push(b": keep-alive\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_stream_chunk_text_joins_parts() {
+        let chunk: GeminiResponse = serde_json::from_str(
+            r#"{"candidates":[{"content":{"role":"model","parts":[{"text":"Hel"},{"text":"lo"}]}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.text().as_deref(), Some("Hello"));
+
+        let usage_only: GeminiResponse =
+            serde_json::from_str(r#"{"usageMetadata":{"totalTokenCount":3}}"#).unwrap();
+        assert_eq!(usage_only.text(), None);
+    }
+
+    #[test]
+    fn test_list_models_response_parsing() {
+        let page: ListModelsResponse = serde_json::from_str(
+            r#"{
+                "models": [
+                    {
+                        "name": "models/gemini-2.0-flash",
+                        "displayName": "Gemini 2.0 Flash",
+                        "inputTokenLimit": 1048576,
+                        "outputTokenLimit": 8192,
+                        "supportedGenerationMethods": ["generateContent", "countTokens"]
+                    },
+                    {
+                        "name": "models/text-embedding-004",
+                        "supportedGenerationMethods": ["embedContent"]
+                    }
+                ],
+                "nextPageToken": "abc"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(page.next_page_token.as_deref(), Some("abc"));
+        assert_eq!(page.models[0].id(), "gemini-2.0-flash");
+        assert_eq!(page.models[0].display_name, "Gemini 2.0 Flash");
+        assert_eq!(page.models[0].input_token_limit, 1048576);
+        assert!(page.models[0].supports_chat());
+        assert!(!page.models[1].supports_chat());
+    }
+
+    #[test]
+    fn test_generation_config_serialization() {
+        let mut client = GeminiClient::new("test_api_key".to_string());
+        let history = [Content::new(Role::User, "Hi")];
+
+        let json = serde_json::to_value(client.build_request(&history).unwrap()).unwrap();
+        assert!(json.get("generationConfig").is_none());
+
+        client.generation_config = GenerationConfig {
+            temperature: Some(0.2),
+            top_k: Some(40),
+            max_output_tokens: Some(256),
+            stop_sequences: vec!["END".to_string()],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(client.build_request(&history).unwrap()).unwrap();
+        let config = &json["generationConfig"];
+        assert_eq!(config["temperature"].as_f64().unwrap() as f32, 0.2);
+        assert_eq!(config["topK"], 40);
+        assert_eq!(config["maxOutputTokens"], 256);
+        assert_eq!(config["stopSequences"][0], "END");
+        assert!(config.get("topP").is_none());
+    }
+
+    #[test]
+    fn test_generation_config_validation() {
+        let mut config = GenerationConfig {
+            temperature: Some(0.7),
+            top_p: Some(0.95),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.temperature = Some(2.5);
+        assert!(config.validate().is_err());
+
+        config.temperature = None;
+        config.top_k = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_system_instruction_serialization() {
+        let mut client = GeminiClient::new("test_api_key".to_string());
+        let history = [Content::new(Role::User, "Review this")];
+
+        let json = serde_json::to_value(client.build_request(&history).unwrap()).unwrap();
+        assert!(json.get("systemInstruction").is_none());
+
+        client.system_instruction = Some("You are a Rust reviewer, answer tersely.".to_string());
+        let json = serde_json::to_value(client.build_request(&history).unwrap()).unwrap();
+        assert_eq!(
+            json["systemInstruction"]["parts"][0]["text"],
+            "You are a Rust reviewer, answer tersely."
+        );
+        assert!(json["systemInstruction"].get("role").is_none());
+    }
+}
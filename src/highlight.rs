@@ -0,0 +1,327 @@
+/// What a piece of source code is, for picking its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    Literal,
+    String,
+    Number,
+    Comment,
+    Function,
+}
+
+/// The lexical rules for one language, just enough to color it.
+struct Syntax {
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    case_insensitive: bool,
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "class", "const", "continue", "default", "delete", "do",
+    "double", "else", "enum", "extern", "float", "for", "goto", "if", "include", "define",
+    "inline", "int", "long", "namespace", "new", "private", "protected", "public", "return",
+    "short", "signed", "sizeof", "static", "struct", "switch", "template", "this", "typedef",
+    "typename", "union", "unsigned", "using", "virtual", "void", "volatile", "while",
+];
+
+const SYNTAXES: &[Syntax] = &[
+    Syntax {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+            "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+            "type", "unsafe", "use", "where", "while",
+        ],
+        literals: &["true", "false", "None", "Some", "Ok", "Err"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        // Single quotes are lifetimes as often as they are chars
+        quotes: &['"'],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["python", "py"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+            "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "self", "try",
+            "while", "with", "yield",
+        ],
+        literals: &["True", "False", "None"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["javascript", "js", "jsx", "typescript", "ts", "tsx"],
+        keywords: &[
+            "async", "await", "break", "case", "catch", "class", "const", "continue", "default",
+            "do", "else", "enum", "export", "extends", "finally", "for", "from", "function", "if",
+            "implements", "import", "in", "instanceof", "interface", "let", "new", "of",
+            "private", "protected", "public", "readonly", "return", "switch", "this", "throw",
+            "try", "type", "typeof", "var", "while", "yield",
+        ],
+        literals: &["true", "false", "null", "undefined"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["go", "golang"],
+        keywords: &[
+            "break", "case", "chan", "const", "continue", "default", "defer", "else",
+            "fallthrough", "for", "func", "go", "goto", "if", "import", "interface", "map",
+            "package", "range", "return", "select", "struct", "switch", "type", "var",
+        ],
+        literals: &["true", "false", "nil", "iota"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["c", "h", "cpp", "c++", "cc", "hpp", "cxx"],
+        keywords: C_KEYWORDS,
+        literals: &["true", "false", "NULL", "nullptr"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["java", "kotlin", "kt", "csharp", "cs", "c#", "swift", "scala"],
+        keywords: &[
+            "abstract", "boolean", "break", "byte", "case", "catch", "char", "class", "const",
+            "continue", "default", "do", "double", "else", "enum", "extends", "final", "finally",
+            "float", "for", "fun", "func", "if", "implements", "import", "int", "interface",
+            "let", "long", "namespace", "new", "override", "package", "private", "protected",
+            "public", "return", "short", "static", "string", "super", "switch", "this", "throw",
+            "throws", "try", "using", "val", "var", "void", "when", "while",
+        ],
+        literals: &["true", "false", "null", "nil"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["sh", "bash", "zsh", "shell", "console", "fish"],
+        keywords: &[
+            "case", "cd", "do", "done", "echo", "elif", "else", "esac", "exit", "export", "fi",
+            "for", "function", "if", "in", "local", "return", "set", "source", "then", "while",
+        ],
+        literals: &["true", "false"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["json", "jsonc"],
+        keywords: &[],
+        literals: &["true", "false", "null"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["toml", "yaml", "yml", "ini", "conf"],
+        keywords: &[],
+        literals: &["true", "false", "null", "yes", "no"],
+        line_comments: &["#", ";"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        names: &["sql", "postgres", "sqlite", "mysql"],
+        keywords: &[
+            "add", "alter", "and", "as", "asc", "by", "create", "delete", "desc", "distinct",
+            "drop", "from", "group", "having", "index", "inner", "insert", "into", "join", "key",
+            "left", "limit", "not", "on", "or", "order", "primary", "right", "select", "set",
+            "table", "union", "update", "values", "where", "with",
+        ],
+        literals: &["true", "false", "null"],
+        line_comments: &["--"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\'', '"'],
+        case_insensitive: true,
+    },
+];
+
+/// Colors a code block line by line, carrying block comments across lines.
+pub struct Highlighter {
+    syntax: Option<&'static Syntax>,
+    in_comment: bool,
+}
+
+impl Highlighter {
+    /// Picks the rules for a fence's language tag; unknown languages stay plain.
+    pub fn new(language: &str) -> Self {
+        let language = language.to_lowercase();
+        let syntax = SYNTAXES.iter().find(|syntax| syntax.names.contains(&language.as_str()));
+        Self { syntax, in_comment: false }
+    }
+
+    pub fn line<'a>(&mut self, line: &'a str) -> Vec<(TokenKind, &'a str)> {
+        let Some(syntax) = self.syntax else {
+            return vec![(TokenKind::Plain, line)];
+        };
+
+        // Byte ranges; neighbours of the same kind are merged into one token
+        let mut tokens: Vec<(TokenKind, usize, usize)> = Vec::new();
+        let mut push = |kind: TokenKind, start: usize, end: usize| match tokens.last_mut() {
+            Some((last, _, last_end)) if *last == kind => *last_end = end,
+            _ => tokens.push((kind, start, end)),
+        };
+
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            if self.in_comment {
+                let (_, close) = syntax.block_comment.expect("only set for block comments");
+                let end = rest.find(close).map_or(rest.len(), |at| {
+                    self.in_comment = false;
+                    at + close.len()
+                });
+                push(TokenKind::Comment, i, i + end);
+                i += end;
+                continue;
+            }
+            if syntax.line_comments.iter().any(|marker| rest.starts_with(marker)) {
+                push(TokenKind::Comment, i, line.len());
+                break;
+            }
+            if let Some((open, _)) = syntax.block_comment {
+                if rest.starts_with(open) {
+                    // Consume the opener so `/*/` does not close itself
+                    self.in_comment = true;
+                    push(TokenKind::Comment, i, i + open.len());
+                    i += open.len();
+                    continue;
+                }
+            }
+
+            let c = rest.chars().next().expect("rest is not empty");
+            let len = if syntax.quotes.contains(&c) {
+                let end = string_end(rest, c);
+                push(TokenKind::String, i, i + end);
+                end
+            } else if c.is_ascii_digit() {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                    .unwrap_or(rest.len());
+                push(TokenKind::Number, i, i + end);
+                end
+            } else if c.is_alphabetic() || c == '_' {
+                let end = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                push(classify(syntax, &rest[..end], &rest[end..]), i, i + end);
+                end
+            } else {
+                push(TokenKind::Plain, i, i + c.len_utf8());
+                c.len_utf8()
+            };
+            i += len;
+        }
+        tokens.into_iter().map(|(kind, start, end)| (kind, &line[start..end])).collect()
+    }
+}
+
+/// Byte length of the string literal at the start of `text`, including its
+/// quotes; an unterminated string runs to the end of the line.
+fn string_end(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (at, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return at + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+fn classify(syntax: &Syntax, word: &str, after: &str) -> TokenKind {
+    let matches = |list: &[&str]| {
+        if syntax.case_insensitive {
+            list.iter().any(|entry| entry.eq_ignore_ascii_case(word))
+        } else {
+            list.contains(&word)
+        }
+    };
+    if matches(syntax.keywords) {
+        TokenKind::Keyword
+    } else if matches(syntax.literals) {
+        TokenKind::Literal
+    } else if after.trim_start().starts_with('(') {
+        TokenKind::Function
+    } else {
+        TokenKind::Plain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds<'a>(tokens: &[(TokenKind, &'a str)]) -> Vec<(TokenKind, &'a str)> {
+        tokens.iter().filter(|(kind, _)| *kind != TokenKind::Plain).copied().collect()
+    }
+
+    #[test]
+    fn test_rust_line() {
+        let mut highlighter = Highlighter::new("rust");
+        let tokens = highlighter.line("let x = parse(\"a \\\" b\", 42); // done");
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                (TokenKind::Keyword, "let"),
+                (TokenKind::Function, "parse"),
+                (TokenKind::String, "\"a \\\" b\""),
+                (TokenKind::Number, "42"),
+                (TokenKind::Comment, "// done"),
+            ]
+        );
+        let text: String = tokens.iter().map(|(_, text)| *text).collect();
+        assert_eq!(text, "let x = parse(\"a \\\" b\", 42); // done");
+    }
+
+    #[test]
+    fn test_block_comment_spans_lines() {
+        let mut highlighter = Highlighter::new("c");
+        assert_eq!(kinds(&highlighter.line("int a; /* start")), vec![
+            (TokenKind::Keyword, "int"),
+            (TokenKind::Comment, "/* start"),
+        ]);
+        assert_eq!(highlighter.line("still */ return"), vec![
+            (TokenKind::Comment, "still */"),
+            (TokenKind::Plain, " "),
+            (TokenKind::Keyword, "return"),
+        ]);
+    }
+
+    #[test]
+    fn test_language_lookup() {
+        let mut sql = Highlighter::new("SQL");
+        assert_eq!(kinds(&sql.line("SELECT name FROM users")), vec![
+            (TokenKind::Keyword, "SELECT"),
+            (TokenKind::Keyword, "FROM"),
+        ]);
+
+        let mut unknown = Highlighter::new("brainfuck");
+        assert_eq!(unknown.line("+[->+<]"), vec![(TokenKind::Plain, "+[->+<]")]);
+    }
+}
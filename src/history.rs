@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use crate::config::get_config_dir;
+
+/// Oldest prompts are dropped once the history grows past this.
+const MAX_ENTRIES: usize = 1000;
+
+/// Previously sent prompts, recalled with Up/Down or Ctrl+R.
+///
+/// Stored as one JSON string per line so multi-line prompts survive.
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    /// Oldest first.
+    entries: Vec<String>,
+    /// Which entry is shown while browsing with Up/Down.
+    position: Option<usize>,
+    /// What was typed before browsing started, restored past the newest entry.
+    draft: String,
+    path: Option<PathBuf>,
+}
+
+/// State of an in-progress Ctrl+R reverse incremental search.
+#[derive(Debug, Clone, Default)]
+pub struct HistorySearch {
+    pub query: String,
+    /// Index of the entry currently matching `query`.
+    pub found: Option<usize>,
+    /// The matching entry, shown in the input box as a preview.
+    pub preview: String,
+}
+
+impl InputHistory {
+    /// Loads the history file from the config directory. A missing or
+    /// unreadable file just means starting with an empty history.
+    pub fn load() -> Self {
+        match get_config_dir() {
+            Ok(dir) => Self::from_path(dir.join("history.jsonl")),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn from_path(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .map(|content| {
+                content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<String>(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            entries,
+            path: Some(path),
+            ..Self::default()
+        }
+    }
+
+    /// Records a sent prompt and ends any Up/Down browsing.
+    pub fn push(&mut self, entry: &str) -> Result<()> {
+        self.reset();
+        if entry.trim().is_empty() || self.entries.last().is_some_and(|last| last == entry) {
+            return Ok(());
+        }
+
+        self.entries.push(entry.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+            self.rewrite()
+        } else {
+            self.append(entry)
+        }
+    }
+
+    /// The entry before the one shown (or the newest), remembering `current`
+    /// as the draft when browsing starts.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let index = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(0) => 0,
+            Some(index) => index - 1,
+        };
+        self.position = Some(index);
+        Some(&self.entries[index])
+    }
+
+    /// The entry after the one shown, or the draft once past the newest.
+    pub fn next(&mut self) -> Option<String> {
+        let index = self.position?;
+        if index + 1 < self.entries.len() {
+            self.position = Some(index + 1);
+            Some(self.entries[index + 1].clone())
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    /// Whether Up/Down is showing an entry rather than the draft.
+    pub fn is_browsing(&self) -> bool {
+        self.position.is_some()
+    }
+
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    /// Newest entry older than `before` that contains `query`.
+    pub fn search(&self, query: &str, before: Option<usize>) -> Option<(usize, &str)> {
+        let end = before.unwrap_or(self.entries.len()).min(self.entries.len());
+        self.entries[..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, entry)| entry.contains(query))
+            .map(|(index, entry)| (index, entry.as_str()))
+    }
+
+    fn append(&self, entry: &str) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create config directory: {:?}", parent))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open history file: {:?}", path))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)
+            .with_context(|| format!("Failed to write history file: {:?}", path))
+    }
+
+    fn rewrite(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let mut content = String::new();
+        for entry in &self.entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write history file: {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> InputHistory {
+        InputHistory {
+            entries: entries.iter().map(|entry| entry.to_string()).collect(),
+            ..InputHistory::default()
+        }
+    }
+
+    #[test]
+    fn test_browsing_restores_draft() {
+        let mut history = history(&["first", "second"]);
+
+        assert_eq!(history.previous("half typed"), Some("second"));
+        assert_eq!(history.previous("second"), Some("first"));
+        assert_eq!(history.previous("first"), Some("first"));
+        assert_eq!(history.next().as_deref(), Some("second"));
+        assert_eq!(history.next().as_deref(), Some("half typed"));
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn test_reverse_search_walks_older_matches() {
+        let history = history(&["cargo build", "git status", "cargo test"]);
+
+        let (index, entry) = history.search("cargo", None).unwrap();
+        assert_eq!((index, entry), (2, "cargo test"));
+        assert_eq!(history.search("cargo", Some(index)), Some((0, "cargo build")));
+        assert_eq!(history.search("cargo", Some(0)), None);
+    }
+
+    #[test]
+    fn test_history_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("gemini-chat-tui-history-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut history = InputHistory::from_path(path.clone());
+        history.push("multi\nline prompt").unwrap();
+        history.push("multi\nline prompt").unwrap();
+        history.push("another").unwrap();
+
+        let reloaded = InputHistory::from_path(path.clone());
+        assert_eq!(reloaded.entries, vec!["multi\nline prompt", "another"]);
+        fs::remove_file(&path).unwrap();
+    }
+}
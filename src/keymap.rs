@@ -0,0 +1,636 @@
+use anyhow::{bail, Context as _, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+/// Where a binding applies. Each view looks keys up in its own context
+/// before falling back to [`Context::Global`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Global,
+    Chat,
+    Editor,
+    Copy,
+    Browser,
+    Search,
+    Popup,
+}
+
+impl Context {
+    /// Every context, in the order the help overlay lists them.
+    pub const ALL: [Context; 7] = [
+        Context::Global,
+        Context::Chat,
+        Context::Editor,
+        Context::Copy,
+        Context::Browser,
+        Context::Search,
+        Context::Popup,
+    ];
+
+    /// Whether one view consults both contexts, so a key may only mean one
+    /// thing across them: chat and editing keys are looked up together, and
+    /// global keys work everywhere.
+    pub fn overlaps(self, other: Context) -> bool {
+        self == other
+            || self == Context::Global
+            || other == Context::Global
+            || matches!((self, other), (Context::Chat, Context::Editor) | (Context::Editor, Context::Chat))
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Context::Global => "Anywhere",
+            Context::Chat => "Chat",
+            Context::Editor => "Editing the message",
+            Context::Copy => "Copy mode",
+            Context::Browser => "Conversation browser",
+            Context::Search => "Prompt search",
+            Context::Popup => "Lists and prompts",
+        }
+    }
+}
+
+/// Something a key press can trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Help,
+    CommandPalette,
+    ToggleSidebar,
+    Quit,
+    Send,
+    Newline,
+    Complete,
+    Cancel,
+    CopyMode,
+    ExternalEditor,
+    HistorySearch,
+    HistoryPrevious,
+    HistoryNext,
+    ScrollPageUp,
+    ScrollPageDown,
+    ScrollTop,
+    ScrollBottom,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    WordLeft,
+    WordRight,
+    LineStart,
+    LineEnd,
+    DeleteBackward,
+    DeleteForward,
+    DeleteWord,
+    DeleteToLineStart,
+    CopyPrevious,
+    CopyNext,
+    CopyNextBlock,
+    CopyPreviousBlock,
+    CopyConfirm,
+    CopyCancel,
+    BrowserPrevious,
+    BrowserNext,
+    BrowserOpen,
+    BrowserFilter,
+    BrowserRename,
+    BrowserDelete,
+    BrowserClose,
+    SearchOlder,
+    SearchAccept,
+    SearchCancel,
+    PopupPrevious,
+    PopupNext,
+    PopupChoose,
+    PopupClose,
+}
+
+impl Action {
+    /// How the config file refers to the action, e.g. `scroll_page_up`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Help => "help",
+            Action::CommandPalette => "command_palette",
+            Action::ToggleSidebar => "toggle_sidebar",
+            Action::Quit => "quit",
+            Action::Send => "send",
+            Action::Newline => "newline",
+            Action::Complete => "complete",
+            Action::Cancel => "cancel",
+            Action::CopyMode => "copy_mode",
+            Action::ExternalEditor => "external_editor",
+            Action::HistorySearch => "history_search",
+            Action::HistoryPrevious => "history_previous",
+            Action::HistoryNext => "history_next",
+            Action::ScrollPageUp => "scroll_page_up",
+            Action::ScrollPageDown => "scroll_page_down",
+            Action::ScrollTop => "scroll_top",
+            Action::ScrollBottom => "scroll_bottom",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::WordLeft => "word_left",
+            Action::WordRight => "word_right",
+            Action::LineStart => "line_start",
+            Action::LineEnd => "line_end",
+            Action::DeleteBackward => "delete_backward",
+            Action::DeleteForward => "delete_forward",
+            Action::DeleteWord => "delete_word",
+            Action::DeleteToLineStart => "delete_to_line_start",
+            Action::CopyPrevious => "copy_previous",
+            Action::CopyNext => "copy_next",
+            Action::CopyNextBlock => "copy_next_block",
+            Action::CopyPreviousBlock => "copy_previous_block",
+            Action::CopyConfirm => "copy_confirm",
+            Action::CopyCancel => "copy_cancel",
+            Action::BrowserPrevious => "browser_previous",
+            Action::BrowserNext => "browser_next",
+            Action::BrowserOpen => "browser_open",
+            Action::BrowserFilter => "browser_filter",
+            Action::BrowserRename => "browser_rename",
+            Action::BrowserDelete => "browser_delete",
+            Action::BrowserClose => "browser_close",
+            Action::SearchOlder => "search_older",
+            Action::SearchAccept => "search_accept",
+            Action::SearchCancel => "search_cancel",
+            Action::PopupPrevious => "popup_previous",
+            Action::PopupNext => "popup_next",
+            Action::PopupChoose => "popup_choose",
+            Action::PopupClose => "popup_close",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        DEFAULT_BINDINGS.iter().map(|(action, _)| *action).find(|action| action.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Help => "Show keys and commands",
+            Action::CommandPalette => "Open the command palette",
+            Action::ToggleSidebar => "Show or hide the conversation browser",
+            Action::Quit => "Quit",
+            Action::Send => "Send the message (or run the /command)",
+            Action::Newline => "Start a new line",
+            Action::Complete => "Complete a /command, or focus the conversation browser",
+            Action::Cancel => "Cancel the pending reply",
+            Action::CopyMode => "Copy a message or code block",
+            Action::ExternalEditor => "Write the message in $VISUAL / $EDITOR",
+            Action::HistorySearch => "Search previously sent prompts",
+            Action::HistoryPrevious => "Recall the previous prompt",
+            Action::HistoryNext => "Recall the next prompt",
+            Action::ScrollPageUp => "Scroll the chat up a page",
+            Action::ScrollPageDown => "Scroll the chat down a page",
+            Action::ScrollTop => "Jump to the oldest message",
+            Action::ScrollBottom => "Jump to the newest message",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::MoveUp => "Move up a line; recalls the previous prompt from the first line",
+            Action::MoveDown => "Move down a line; recalls the next prompt from the last line",
+            Action::WordLeft => "Move to the previous word",
+            Action::WordRight => "Move to the next word",
+            Action::LineStart => "Go to the start of the line (the oldest message if empty)",
+            Action::LineEnd => "Go to the end of the line (the newest message if empty)",
+            Action::DeleteBackward => "Delete the character before the cursor",
+            Action::DeleteForward => "Delete the character under the cursor",
+            Action::DeleteWord => "Delete the word before the cursor",
+            Action::DeleteToLineStart => "Delete to the start of the line",
+            Action::CopyPrevious => "Pick the previous message",
+            Action::CopyNext => "Pick the next message",
+            Action::CopyNextBlock => "Pick the next code block",
+            Action::CopyPreviousBlock => "Pick the previous code block",
+            Action::CopyConfirm => "Copy the pick",
+            Action::CopyCancel => "Stop copying",
+            Action::BrowserPrevious => "Select the previous conversation",
+            Action::BrowserNext => "Select the next conversation",
+            Action::BrowserOpen => "Open the selected conversation",
+            Action::BrowserFilter => "Filter conversations by title",
+            Action::BrowserRename => "Rename the selected conversation",
+            Action::BrowserDelete => "Delete the selected conversation (y confirms)",
+            Action::BrowserClose => "Back to the message input",
+            Action::SearchOlder => "Find an older match",
+            Action::SearchAccept => "Put the match in the input",
+            Action::SearchCancel => "Stop searching",
+            Action::PopupPrevious => "Select the previous entry",
+            Action::PopupNext => "Select the next entry",
+            Action::PopupChoose => "Choose the entry, or confirm the prompt",
+            Action::PopupClose => "Close the list, or cancel the prompt",
+        }
+    }
+
+    pub fn context(self) -> Context {
+        match self {
+            Action::Help | Action::CommandPalette | Action::ToggleSidebar | Action::Quit => Context::Global,
+            Action::Send
+            | Action::Newline
+            | Action::Complete
+            | Action::Cancel
+            | Action::CopyMode
+            | Action::ExternalEditor
+            | Action::HistorySearch
+            | Action::HistoryPrevious
+            | Action::HistoryNext
+            | Action::ScrollPageUp
+            | Action::ScrollPageDown
+            | Action::ScrollTop
+            | Action::ScrollBottom => Context::Chat,
+            Action::MoveLeft
+            | Action::MoveRight
+            | Action::MoveUp
+            | Action::MoveDown
+            | Action::WordLeft
+            | Action::WordRight
+            | Action::LineStart
+            | Action::LineEnd
+            | Action::DeleteBackward
+            | Action::DeleteForward
+            | Action::DeleteWord
+            | Action::DeleteToLineStart => Context::Editor,
+            Action::CopyPrevious
+            | Action::CopyNext
+            | Action::CopyNextBlock
+            | Action::CopyPreviousBlock
+            | Action::CopyConfirm
+            | Action::CopyCancel => Context::Copy,
+            Action::BrowserPrevious
+            | Action::BrowserNext
+            | Action::BrowserOpen
+            | Action::BrowserFilter
+            | Action::BrowserRename
+            | Action::BrowserDelete
+            | Action::BrowserClose => Context::Browser,
+            Action::SearchOlder | Action::SearchAccept | Action::SearchCancel => Context::Search,
+            Action::PopupPrevious | Action::PopupNext | Action::PopupChoose | Action::PopupClose => {
+                Context::Popup
+            }
+        }
+    }
+}
+
+/// Keys bound to each action out of the box, grouped by context. The event
+/// loop, the help overlay and the palette all read from this table.
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::Help, &["f1", "?"]),
+    (Action::CommandPalette, &["ctrl+k"]),
+    (Action::ToggleSidebar, &["ctrl+b"]),
+    (Action::Quit, &["ctrl+c"]),
+    (Action::Send, &["enter"]),
+    (Action::Newline, &["shift+enter", "alt+enter"]),
+    (Action::Complete, &["tab"]),
+    (Action::Cancel, &["esc"]),
+    (Action::CopyMode, &["ctrl+y"]),
+    (Action::ExternalEditor, &["ctrl+e"]),
+    (Action::HistorySearch, &["ctrl+r"]),
+    (Action::HistoryPrevious, &["ctrl+p"]),
+    (Action::HistoryNext, &["ctrl+n"]),
+    (Action::ScrollPageUp, &["pageup"]),
+    (Action::ScrollPageDown, &["pagedown"]),
+    (Action::ScrollTop, &["ctrl+home"]),
+    (Action::ScrollBottom, &["ctrl+end"]),
+    (Action::MoveLeft, &["left"]),
+    (Action::MoveRight, &["right"]),
+    (Action::MoveUp, &["up"]),
+    (Action::MoveDown, &["down"]),
+    (Action::WordLeft, &["ctrl+left"]),
+    (Action::WordRight, &["ctrl+right"]),
+    (Action::LineStart, &["home"]),
+    (Action::LineEnd, &["end"]),
+    (Action::DeleteBackward, &["backspace"]),
+    (Action::DeleteForward, &["delete"]),
+    (Action::DeleteWord, &["ctrl+w"]),
+    (Action::DeleteToLineStart, &["ctrl+u"]),
+    (Action::CopyPrevious, &["up", "k"]),
+    (Action::CopyNext, &["down", "j"]),
+    (Action::CopyNextBlock, &["tab", "right", "l"]),
+    (Action::CopyPreviousBlock, &["backtab", "left", "h"]),
+    (Action::CopyConfirm, &["y", "enter"]),
+    (Action::CopyCancel, &["esc"]),
+    (Action::BrowserPrevious, &["up", "k"]),
+    (Action::BrowserNext, &["down", "j"]),
+    (Action::BrowserOpen, &["enter"]),
+    (Action::BrowserFilter, &["/"]),
+    (Action::BrowserRename, &["r"]),
+    (Action::BrowserDelete, &["d", "delete"]),
+    (Action::BrowserClose, &["tab", "esc"]),
+    (Action::SearchOlder, &["ctrl+r"]),
+    (Action::SearchAccept, &["enter", "left", "right", "home", "end"]),
+    (Action::SearchCancel, &["esc", "ctrl+g"]),
+    (Action::PopupPrevious, &["up", "ctrl+p"]),
+    (Action::PopupNext, &["down", "ctrl+n"]),
+    (Action::PopupChoose, &["enter"]),
+    (Action::PopupClose, &["esc", "q"]),
+];
+
+/// A key together with its modifiers, written like `ctrl+k` or `shift+enter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().to_lowercase();
+        let mut parts: Vec<&str> = text.split('+').collect();
+        // `ctrl++` and a lone `+` name the plus key itself
+        if parts.len() > 1 && parts[parts.len() - 1].is_empty() {
+            parts.pop();
+            let last = parts.len() - 1;
+            parts[last] = "+";
+        }
+        let Some((key, modifier_names)) = parts.split_last() else {
+            bail!("empty key binding");
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names {
+            modifiers |= match *name {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => bail!("unknown modifier \"{}\" in \"{}\"", other, text),
+            };
+        }
+
+        let code = match *key {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            function if function.starts_with('f') && function[1..].parse::<u8>().is_ok() => {
+                KeyCode::F(function[1..].parse().unwrap_or(1))
+            }
+            single if single.chars().count() == 1 => KeyCode::Char(single.chars().next().unwrap_or(' ')),
+            other => bail!("unknown key \"{}\" in \"{}\"", other, text),
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// Shift is part of the character itself (`?` is shift+/), so it is
+    /// ignored for character keys and for Shift+Tab.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// A character without Ctrl or Alt, which types text where text goes.
+    pub fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let pressed = Self::new(key.code, key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT));
+        match (self.code, pressed.code) {
+            (KeyCode::Char(a), KeyCode::Char(b)) => {
+                a.eq_ignore_ascii_case(&b) && self.modifiers == pressed.modifiers
+            }
+            _ => *self == pressed,
+        }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// The keys for one action in the config file: `"ctrl+s"` or a list like
+/// `["enter", "ctrl+j"]`. An empty list leaves the action unbound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBindings {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBindings {
+    fn chords(&self) -> &[String] {
+        match self {
+            KeyBindings::One(chord) => std::slice::from_ref(chord),
+            KeyBindings::Many(chords) => chords,
+        }
+    }
+}
+
+/// Which action each key triggers.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeyChord, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::with_overrides(&BTreeMap::new()).expect("default bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// The default bindings with the actions named in `overrides` rebound.
+    /// Fails on unknown actions or keys, on a plain character for a chat or
+    /// editing action (it would only ever be typed), and on a key that would
+    /// mean two things in the same view.
+    pub fn with_overrides(overrides: &BTreeMap<String, KeyBindings>) -> Result<Self> {
+        for name in overrides.keys() {
+            if Action::from_name(name).is_none() {
+                let known: Vec<&str> = DEFAULT_BINDINGS.iter().map(|(action, _)| action.name()).collect();
+                bail!("Unknown action \"{}\" (available: {})", name, known.join(", "));
+            }
+        }
+
+        let mut bindings: Vec<(KeyChord, Action)> = Vec::new();
+        for (action, defaults) in DEFAULT_BINDINGS {
+            let chords: Vec<&str> = match overrides.get(action.name()) {
+                Some(keys) => keys.chords().iter().map(String::as_str).collect(),
+                None => defaults.to_vec(),
+            };
+            for chord in chords {
+                let chord = KeyChord::parse(chord).with_context(|| format!("Invalid key for \"{}\"", action.name()))?;
+                if chord.is_text() && matches!(action.context(), Context::Chat | Context::Editor) {
+                    bail!(
+                        "{} cannot be bound to \"{}\": plain characters type into the input box; add ctrl or alt",
+                        chord,
+                        action.name()
+                    );
+                }
+                if let Some((_, other)) = bindings
+                    .iter()
+                    .find(|(bound, other)| *bound == chord && other.context().overlaps(action.context()))
+                {
+                    bail!(
+                        "{} is bound to both \"{}\" and \"{}\"; rebind one of them",
+                        chord,
+                        other.name(),
+                        action.name()
+                    );
+                }
+                if !bindings.iter().any(|(bound, other)| *bound == chord && other == action) {
+                    bindings.push((chord, *action));
+                }
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    /// The action `key` triggers in the first of `contexts` that binds it.
+    pub fn action(&self, contexts: &[Context], key: &KeyEvent) -> Option<Action> {
+        contexts.iter().find_map(|context| {
+            self.bindings
+                .iter()
+                .find(|(chord, action)| action.context() == *context && chord.matches(key))
+                .map(|(_, action)| *action)
+        })
+    }
+
+    /// Every action in table order with the keys bound to it.
+    pub fn bindings(&self) -> Vec<(Action, Vec<KeyChord>)> {
+        DEFAULT_BINDINGS
+            .iter()
+            .map(|(action, _)| (*action, self.chords(*action)))
+            .collect()
+    }
+
+    pub fn chords(&self, action: Action) -> Vec<KeyChord> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(chord, _)| *chord)
+            .collect()
+    }
+}
+
+/// The keys of a binding joined for display, e.g. `Shift+Enter / Alt+Enter`.
+pub fn describe(chords: &[KeyChord]) -> String {
+    chords.iter().map(KeyChord::to_string).collect::<Vec<_>>().join(" / ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let chord = KeyChord::parse("Ctrl+K").unwrap();
+        assert_eq!(chord, KeyChord::new(KeyCode::Char('k'), KeyModifiers::CONTROL));
+        assert_eq!(chord.to_string(), "Ctrl+K");
+        assert_eq!(KeyChord::parse("shift+enter").unwrap().to_string(), "Shift+Enter");
+        assert_eq!(KeyChord::parse("f1").unwrap().code, KeyCode::F(1));
+        assert_eq!(KeyChord::parse("?").unwrap().to_string(), "?");
+        assert_eq!(KeyChord::parse("ctrl++").unwrap().code, KeyCode::Char('+'));
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("ctrl+nothing").is_err());
+    }
+
+    #[test]
+    fn test_keymap_dispatch() {
+        let keymap = Keymap::default();
+        let chat = [Context::Chat, Context::Editor, Context::Global];
+        assert_eq!(keymap.action(&chat, &press(KeyCode::Enter, KeyModifiers::NONE)), Some(Action::Send));
+        assert_eq!(keymap.action(&chat, &press(KeyCode::Enter, KeyModifiers::SHIFT)), Some(Action::Newline));
+        assert_eq!(keymap.action(&chat, &press(KeyCode::Char('k'), KeyModifiers::CONTROL)), Some(Action::CommandPalette));
+        assert_eq!(keymap.action(&chat, &press(KeyCode::Char('k'), KeyModifiers::NONE)), None);
+        assert_eq!(describe(&keymap.chords(Action::Newline)), "Shift+Enter / Alt+Enter");
+
+        // The same key means different things in different views
+        let browser = [Context::Browser, Context::Global];
+        assert_eq!(keymap.action(&browser, &press(KeyCode::Char('k'), KeyModifiers::NONE)), Some(Action::BrowserPrevious));
+        assert_eq!(keymap.action(&browser, &press(KeyCode::Esc, KeyModifiers::NONE)), Some(Action::BrowserClose));
+        let copy = [Context::Copy, Context::Global];
+        assert_eq!(keymap.action(&copy, &press(KeyCode::BackTab, KeyModifiers::SHIFT)), Some(Action::CopyPreviousBlock));
+    }
+
+    #[test]
+    fn test_every_action_is_listed_once() {
+        for (index, (action, _)) in DEFAULT_BINDINGS.iter().enumerate() {
+            assert!(
+                !DEFAULT_BINDINGS[..index].iter().any(|(earlier, _)| earlier == action),
+                "{:?} is listed twice",
+                action
+            );
+            assert_eq!(Action::from_name(action.name()), Some(*action));
+        }
+    }
+
+    #[test]
+    fn test_overrides() {
+        fn overrides(entries: &[(&str, KeyBindings)]) -> BTreeMap<String, KeyBindings> {
+            entries.iter().map(|(name, keys)| (name.to_string(), keys.clone())).collect()
+        }
+        fn error(entries: &[(&str, KeyBindings)]) -> String {
+            Keymap::with_overrides(&overrides(entries)).unwrap_err().to_string()
+        }
+        let chat = [Context::Chat, Context::Editor, Context::Global];
+
+        // Swapping send and newline, tmux-style quitting
+        let keymap = Keymap::with_overrides(&overrides(&[
+            ("send", KeyBindings::One("ctrl+j".into())),
+            ("newline", KeyBindings::Many(vec!["enter".into()])),
+            ("quit", KeyBindings::Many(vec!["ctrl+q".into(), "ctrl+c".into()])),
+        ]))
+        .unwrap();
+        assert_eq!(keymap.action(&chat, &press(KeyCode::Enter, KeyModifiers::NONE)), Some(Action::Newline));
+        assert_eq!(keymap.action(&chat, &press(KeyCode::Char('j'), KeyModifiers::CONTROL)), Some(Action::Send));
+        assert_eq!(keymap.action(&chat, &press(KeyCode::Char('q'), KeyModifiers::CONTROL)), Some(Action::Quit));
+        assert_eq!(describe(&keymap.chords(Action::Quit)), "Ctrl+Q / Ctrl+C");
+
+        // An empty list unbinds
+        let keymap = Keymap::with_overrides(&overrides(&[("cancel", KeyBindings::Many(vec![]))])).unwrap();
+        assert!(keymap.chords(Action::Cancel).is_empty());
+
+        assert!(error(&[("sned", KeyBindings::One("enter".into()))]).starts_with("Unknown action \"sned\""));
+        assert_eq!(error(&[("send", KeyBindings::One("ctrl+nope".into()))]), "Invalid key for \"send\"");
+        assert_eq!(
+            error(&[("newline", KeyBindings::One("enter".into()))]),
+            "Enter is bound to both \"send\" and \"newline\"; rebind one of them"
+        );
+        // Global keys clash with every view, other views do not clash
+        assert!(error(&[("quit", KeyBindings::One("up".into()))]).contains("\"move_up\""));
+        // Plain characters would only ever be typed into the input box
+        assert_eq!(
+            error(&[("send", KeyBindings::One("s".into()))]),
+            "s cannot be bound to \"send\": plain characters type into the input box; add ctrl or alt"
+        );
+        assert!(error(&[("delete_word", KeyBindings::One("shift+w".into()))]).contains("\"delete_word\""));
+        assert!(Keymap::with_overrides(&overrides(&[("send", KeyBindings::One("alt+s".into()))])).is_ok());
+        assert!(Keymap::with_overrides(&overrides(&[("copy_confirm", KeyBindings::One("ctrl+p".into()))])).is_ok());
+    }
+}
@@ -1,12 +1,28 @@
 mod app;
+mod clipboard;
+mod commands;
 mod config;
 mod demo;
+mod editor;
+mod external_editor;
 mod gemini;
+mod highlight;
+mod history;
+mod keymap;
+mod markdown;
+mod palette;
+mod session;
+mod theme;
 mod ui;
+mod vim;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use config::Config;
+use keymap::Keymap;
+use history::InputHistory;
+use session::SessionStore;
+use theme::Theme;
 
 #[derive(Parser)]
 #[command(name = "gemini-chat-tui")]
@@ -23,6 +39,46 @@ struct Cli {
     /// Run in demo mode (shows UI without API key)
     #[arg(long)]
     demo: bool,
+    
+    /// Model to chat with for this run (e.g. gemini-2.0-flash, pro, flash, flash-lite)
+    #[arg(long)]
+    model: Option<String>,
+    
+    /// Sampling temperature for this run (0.0-2.0)
+    #[arg(long)]
+    temperature: Option<f32>,
+    
+    /// Nucleus sampling probability for this run (0.0-1.0)
+    #[arg(long)]
+    top_p: Option<f32>,
+    
+    /// Top-k sampling for this run
+    #[arg(long)]
+    top_k: Option<u32>,
+    
+    /// Maximum number of tokens in each reply for this run
+    #[arg(long)]
+    max_output_tokens: Option<u32>,
+    
+    /// Stop generating at this sequence (repeat for several)
+    #[arg(long = "stop")]
+    stop_sequences: Vec<String>,
+    
+    /// Persona (named system instruction from the config file) for this run
+    #[arg(long)]
+    persona: Option<String>,
+    
+    /// Reopen the last conversation, or the one with the given id
+    #[arg(long, value_name = "ID", num_args = 0..=1, default_missing_value = "")]
+    resume: Option<String>,
+    
+    /// List the models available to your API key and exit
+    #[arg(long)]
+    list_models: bool,
+    
+    /// Wait for the complete reply instead of streaming it
+    #[arg(long)]
+    no_stream: bool,
 }
 
 #[tokio::main]
@@ -38,9 +94,9 @@ async fn main() -> Result<()> {
     }
     
     let mut config = if cli.reset_config {
-        Config { api_key: String::new() }
+        Config::default()
     } else {
-        Config::load().unwrap_or_else(|_| Config { api_key: String::new() })
+        Config::load()?
     };
     
     // Handle API key setup
@@ -51,9 +107,82 @@ async fn main() -> Result<()> {
         config.set_api_key(api_key)?;
     }
     
+    if cli.list_models {
+        let client = gemini::GeminiClient::new(config.api_key);
+        let models = client.list_models().await?;
+        print_models(&models);
+        return Ok(());
+    }
+    
+    // Per-run overrides are not saved to the config file
+    if cli.no_stream {
+        config.stream = false;
+    }
+    if let Some(model) = cli.model {
+        config.model = gemini::resolve_model(&model);
+    }
+    if cli.temperature.is_some() {
+        config.generation.temperature = cli.temperature;
+    }
+    if cli.top_p.is_some() {
+        config.generation.top_p = cli.top_p;
+    }
+    if cli.top_k.is_some() {
+        config.generation.top_k = cli.top_k;
+    }
+    if cli.max_output_tokens.is_some() {
+        config.generation.max_output_tokens = cli.max_output_tokens;
+    }
+    if !cli.stop_sequences.is_empty() {
+        config.generation.stop_sequences = cli.stop_sequences;
+    }
+    config.generation.validate()?;
+    if let Some(persona) = cli.persona {
+        config.persona = Some(persona);
+    }
+    if let Some(persona) = &config.persona {
+        config.check_persona(persona)?;
+    }
+    // https://no-color.org: any non-empty value turns colors off
+    if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        config.theme = "monochrome".to_string();
+        config.colors.clear();
+    }
+    let keymap = Keymap::with_overrides(&config.keymap).context("Invalid keymap in the config file")?;
+    let theme = Theme::load(&config.theme)
+        .and_then(|theme| theme.with_colors(&config.colors))
+        .context("Invalid theme in the config file")?;
+    
+    let mut sessions = SessionStore::open()?;
+    let session = match cli.resume.as_deref() {
+        None => sessions.new_session(),
+        Some("") => sessions.load_latest()?.unwrap_or_else(|| {
+            println!("No saved conversations yet, starting a new one");
+            sessions.new_session()
+        }),
+        Some(id) => sessions.load(id)?,
+    };
+    
     // Start the TUI application
-    let mut app = app::App::new(config.api_key);
+    let mut app = app::App::new(config, keymap, theme, sessions, session, InputHistory::load());
     app.run().await?;
     
     Ok(())
 }
+
+fn print_models(models: &[gemini::ModelInfo]) {
+    println!(
+        "{:<40} {:<32} {:>10} {:>8}  METHODS",
+        "MODEL", "NAME", "INPUT", "OUTPUT"
+    );
+    for model in models {
+        println!(
+            "{:<40} {:<32} {:>10} {:>8}  {}",
+            model.id(),
+            model.display_name,
+            model.input_token_limit,
+            model.output_token_limit,
+            model.supported_generation_methods.join(", ")
+        );
+    }
+}
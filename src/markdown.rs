@@ -0,0 +1,775 @@
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    highlight::{Highlighter, TokenKind},
+    theme::Theme,
+};
+
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Narrower table columns than this switch to one record per row.
+const MIN_COLUMN_WIDTH: usize = 4;
+
+/// Renders Markdown `text` into styled lines no wider than `width` columns,
+/// marking the code block at index `selected_code` as picked for copying.
+pub fn render(text: &str, width: usize, selected_code: Option<usize>, theme: &Theme) -> Vec<Line<'static>> {
+    let mut renderer = Renderer::new(width.max(1), theme);
+    renderer.selected_code = selected_code;
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS | Options::ENABLE_TABLES;
+    for event in Parser::new_ext(text, options) {
+        renderer.handle(event);
+    }
+    renderer.finish()
+}
+
+/// The contents of every fenced or indented code block in `text`.
+pub fn code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<String> = None;
+    for event in Parser::new(text) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => current = Some(String::new()),
+            Event::Text(text) => {
+                if let Some(block) = current.as_mut() {
+                    block.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+    // A fence that is still streaming in
+    blocks.extend(current);
+    blocks
+}
+
+fn text_style(theme: &Theme) -> Style {
+    Style::default().fg(theme.text)
+}
+
+fn code_style(theme: &Theme) -> Style {
+    Style::default().fg(theme.code)
+}
+
+/// Code blocks sit on their own background to set them apart from prose.
+fn code_block_style(theme: &Theme) -> Style {
+    Style::default().fg(theme.text).bg(theme.code_background)
+}
+
+fn token_style(kind: TokenKind, theme: &Theme) -> Style {
+    let style = code_block_style(theme);
+    match kind {
+        TokenKind::Plain => style,
+        TokenKind::Keyword => style.fg(theme.keyword),
+        TokenKind::Literal | TokenKind::Number => style.fg(theme.literal),
+        TokenKind::String => style.fg(theme.string),
+        TokenKind::Comment => style.fg(theme.comment).add_modifier(Modifier::ITALIC),
+        TokenKind::Function => style.fg(theme.function),
+    }
+}
+
+fn muted_style(theme: &Theme) -> Style {
+    Style::default().fg(theme.muted)
+}
+
+fn heading_style(level: HeadingLevel, theme: &Theme) -> Style {
+    let style = text_style(theme).add_modifier(Modifier::BOLD);
+    match level {
+        HeadingLevel::H1 => style.fg(theme.heading).add_modifier(Modifier::UNDERLINED),
+        HeadingLevel::H2 => style.fg(theme.subheading),
+        _ => style,
+    }
+}
+
+struct CodeBlock {
+    language: String,
+    text: String,
+}
+
+struct Table {
+    alignments: Vec<Alignment>,
+    /// The header row first, then the body; each cell is its inline content.
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+}
+
+struct Renderer<'a> {
+    theme: &'a Theme,
+    width: usize,
+    lines: Vec<Line<'static>>,
+    /// Inline content of the current block, wrapped when the block ends.
+    spans: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    /// One entry per open list: the next number, or `None` for bullets.
+    lists: Vec<Option<u64>>,
+    /// Hanging indent of each open list item.
+    indents: Vec<usize>,
+    /// Marker still to be printed on the first line of the current item.
+    marker: Option<String>,
+    quote_depth: usize,
+    code: Option<CodeBlock>,
+    table: Option<Table>,
+    /// Target of the open link and where its text starts in `spans`.
+    link: Option<(String, usize)>,
+    /// A blank line separates the next block from the previous one.
+    gap: bool,
+    /// Code blocks seen so far, to spot the selected one.
+    code_count: usize,
+    selected_code: Option<usize>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(width: usize, theme: &'a Theme) -> Self {
+        Self {
+            theme,
+            width,
+            lines: Vec::new(),
+            spans: Vec::new(),
+            styles: Vec::new(),
+            lists: Vec::new(),
+            indents: Vec::new(),
+            marker: None,
+            quote_depth: 0,
+            code: None,
+            table: None,
+            link: None,
+            gap: false,
+            code_count: 0,
+            selected_code: None,
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_else(|| text_style(self.theme))
+    }
+
+    fn push_style(&mut self, modifier: Modifier) {
+        self.styles.push(self.style().add_modifier(modifier));
+    }
+
+    fn push_text(&mut self, text: impl Into<String>, style: Style) {
+        self.spans.push(Span::styled(text.into(), style));
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match &mut self.code {
+                Some(code) => code.text.push_str(&text),
+                None => self.push_text(text.to_string(), self.style()),
+            },
+            Event::Code(code) => self.push_text(code.to_string(), code_style(self.theme)),
+            Event::Html(html) | Event::InlineHtml(html) => self.push_text(html.to_string(), self.style()),
+            Event::SoftBreak => self.push_text(" ", self.style()),
+            Event::HardBreak => self.push_text("\n", self.style()),
+            Event::Rule => {
+                self.start_block();
+                let mut spans = self.prefix();
+                spans.push(Span::styled("─".repeat(self.content_width()), muted_style(self.theme)));
+                self.lines.push(Line::from(spans));
+                self.gap = true;
+            }
+            Event::TaskListMarker(checked) => {
+                self.push_text(if checked { "☑ " } else { "☐ " }, self.style());
+            }
+            Event::FootnoteReference(name) => self.push_text(format!("[^{}]", name), muted_style(self.theme)),
+            Event::InlineMath(math) | Event::DisplayMath(math) => {
+                self.push_text(math.to_string(), code_style(self.theme));
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph | Tag::HtmlBlock => self.start_block(),
+            Tag::Heading { level, .. } => {
+                self.start_block();
+                self.styles.push(heading_style(level, self.theme));
+            }
+            Tag::BlockQuote(_) => {
+                self.start_block();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some(CodeBlock { language, text: String::new() });
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.start_block();
+                } else {
+                    self.flush();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => format!("{} ", BULLETS[depth % BULLETS.len()]),
+                };
+                self.indents.push(marker.width());
+                self.marker = Some(marker);
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table { alignments, rows: Vec::new() });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+                if matches!(tag, Tag::TableHead) {
+                    self.push_style(Modifier::BOLD);
+                }
+            }
+            Tag::Emphasis => self.push_style(Modifier::ITALIC),
+            Tag::Strong => self.push_style(Modifier::BOLD),
+            Tag::Strikethrough => self.push_style(Modifier::CROSSED_OUT),
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.styles.push(self.style().fg(self.theme.link).add_modifier(Modifier::UNDERLINED));
+                self.link = Some((dest_url.to_string(), self.spans.len()));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::HtmlBlock => {
+                self.flush();
+                self.gap = true;
+            }
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.styles.pop();
+                self.gap = true;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.gap = true;
+            }
+            TagEnd::CodeBlock => {
+                if let Some(code) = self.code.take() {
+                    self.push_code(&code);
+                }
+                self.gap = true;
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.gap = true;
+                }
+            }
+            TagEnd::Item => {
+                self.flush();
+                self.indents.pop();
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.push_table(table);
+                }
+                self.gap = true;
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.spans);
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(cell);
+                }
+            }
+            TagEnd::TableHead | TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link | TagEnd::Image => {
+                self.styles.pop();
+                if let Some((url, start)) = self.link.take() {
+                    let label: String = self.spans[start..].iter().map(|span| span.content.as_ref()).collect();
+                    // Bare URLs and in-page anchors already say all there is to say
+                    if !url.is_empty() && label != url && !url.starts_with('#') {
+                        self.push_text(format!(" ({})", url), muted_style(self.theme));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Separates a new block from the previous one.
+    fn start_block(&mut self) {
+        self.flush();
+        if self.gap && !self.lines.is_empty() {
+            self.lines.push(Line::from(self.quote_bars()));
+        }
+        self.gap = false;
+    }
+
+    fn quote_bars(&self) -> Vec<Span<'static>> {
+        (0..self.quote_depth).map(|_| Span::styled("│ ", muted_style(self.theme))).collect()
+    }
+
+    /// Quote bars plus list indentation, with the item marker on an item's first line.
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut spans = self.quote_bars();
+        let indent: usize = self.indents.iter().sum();
+        match self.marker.take() {
+            Some(marker) => {
+                let outer = indent - marker.width();
+                spans.push(Span::raw(" ".repeat(outer)));
+                spans.push(Span::styled(marker, Style::default().fg(self.theme.list_marker)));
+            }
+            None if indent > 0 => spans.push(Span::raw(" ".repeat(indent))),
+            None => {}
+        }
+        spans
+    }
+
+    fn content_width(&self) -> usize {
+        let used = self.quote_depth * 2 + self.indents.iter().sum::<usize>();
+        self.width.saturating_sub(used).max(1)
+    }
+
+    /// Wraps the pending inline content into lines.
+    fn flush(&mut self) {
+        if self.spans.is_empty() {
+            // An empty list item still shows its marker
+            if self.marker.is_some() {
+                let spans = self.prefix();
+                self.lines.push(Line::from(spans));
+            }
+            return;
+        }
+        let spans = std::mem::take(&mut self.spans);
+        for row in wrap_spans(&spans, self.content_width()) {
+            let mut line = self.prefix();
+            line.extend(row);
+            self.lines.push(Line::from(line));
+        }
+    }
+
+    /// Code keeps its indentation and line breaks on a shaded block; lines
+    /// wider than the bubble are cut off with a marker instead of wrapped.
+    fn push_code(&mut self, code: &CodeBlock) {
+        let mut highlighter = Highlighter::new(&code.language);
+        let sources: Vec<String> = code
+            .text
+            .trim_end_matches('\n')
+            .split('\n')
+            .map(|line| line.replace('\t', "    "))
+            .collect();
+        let rows: Vec<Vec<Span<'static>>> = sources
+            .iter()
+            .map(|source| {
+                highlighter
+                    .line(source)
+                    .into_iter()
+                    .map(|(kind, text)| Span::styled(text.to_string(), token_style(kind, self.theme)))
+                    .collect()
+            })
+            .collect();
+
+        // The label row names the language, or flags the block picked for copying
+        let selected = self.selected_code == Some(self.code_count);
+        self.code_count += 1;
+        let label = if selected {
+            let language = if code.language.is_empty() { "code" } else { &code.language };
+            Some(Span::styled(
+                format!("▶ {}", language),
+                Style::default().fg(self.theme.selected).bg(self.theme.code_background).add_modifier(Modifier::BOLD),
+            ))
+        } else if !code.language.is_empty() {
+            Some(Span::styled(code.language.clone(), muted_style(self.theme).bg(self.theme.code_background)))
+        } else {
+            None
+        };
+
+        // One column of padding on each side of the code
+        let widest = rows
+            .iter()
+            .map(|row| row.iter().map(Span::width).sum::<usize>())
+            .chain(label.as_ref().map(Span::width))
+            .max()
+            .unwrap_or(0);
+        let block_width = (widest + 2).min(self.content_width());
+        let inner = block_width.saturating_sub(2).max(1);
+
+        if let Some(label) = label {
+            self.push_code_row(truncate_spans(vec![label], inner, muted_style(self.theme).bg(self.theme.code_background)), inner);
+        }
+        for row in rows {
+            self.push_code_row(truncate_spans(row, inner, muted_style(self.theme).bg(self.theme.code_background)), inner);
+        }
+    }
+
+    fn push_code_row(&mut self, row: Vec<Span<'static>>, inner: usize) {
+        let used: usize = row.iter().map(Span::width).sum();
+        let mut line = self.prefix();
+        line.push(Span::styled(" ", code_block_style(self.theme)));
+        line.extend(row);
+        line.push(Span::styled(" ".repeat(inner.saturating_sub(used) + 1), code_block_style(self.theme)));
+        self.lines.push(Line::from(line));
+    }
+
+    /// Draws a table with box borders, shrinking the widest columns until it
+    /// fits; when even that is too cramped each row becomes a short record.
+    fn push_table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let mut widths = vec![0; columns];
+        for row in &table.rows {
+            for (column, cell) in row.iter().enumerate() {
+                widths[column] = widths[column].max(spans_width(cell));
+            }
+        }
+
+        let chrome = 3 * columns + 1;
+        let width = self.content_width();
+        if width < chrome + columns * MIN_COLUMN_WIDTH {
+            self.push_table_records(table);
+            return;
+        }
+        let room = width - chrome;
+        while widths.iter().sum::<usize>() > room {
+            let widest = (0..columns).max_by_key(|&column| widths[column]).unwrap_or(0);
+            widths[widest] -= 1;
+        }
+
+        self.push_table_border(&widths, "┌", "┬", "┐");
+        for (index, row) in table.rows.into_iter().enumerate() {
+            let mut line = self.prefix();
+            line.push(Span::styled("│", muted_style(self.theme)));
+            let mut cells = row.into_iter();
+            for (column, &column_width) in widths.iter().enumerate() {
+                let cell = truncate_spans(cells.next().unwrap_or_default(), column_width, muted_style(self.theme));
+                let slack = column_width.saturating_sub(spans_width(&cell));
+                let left = match table.alignments.get(column) {
+                    Some(Alignment::Right) => slack,
+                    Some(Alignment::Center) => slack / 2,
+                    _ => 0,
+                };
+                line.push(Span::raw(" ".repeat(left + 1)));
+                line.extend(cell);
+                line.push(Span::raw(" ".repeat(slack - left + 1)));
+                line.push(Span::styled("│", muted_style(self.theme)));
+            }
+            self.lines.push(Line::from(line));
+            if index == 0 {
+                self.push_table_border(&widths, "├", "┼", "┤");
+            }
+        }
+        self.push_table_border(&widths, "└", "┴", "┘");
+    }
+
+    fn push_table_border(&mut self, widths: &[usize], left: &str, middle: &str, right: &str) {
+        let segments: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+        let mut line = self.prefix();
+        line.push(Span::styled(format!("{}{}{}", left, segments.join(middle), right), muted_style(self.theme)));
+        self.lines.push(Line::from(line));
+    }
+
+    /// Narrow fallback: every body row as `Header: value` lines, with rules
+    /// between the rows.
+    fn push_table_records(&mut self, table: Table) {
+        let mut rows = table.rows.into_iter();
+        let header = rows.next().unwrap_or_default();
+        let width = self.content_width();
+        for (index, row) in rows.enumerate() {
+            if index > 0 {
+                let mut line = self.prefix();
+                line.push(Span::styled("─".repeat(width), muted_style(self.theme)));
+                self.lines.push(Line::from(line));
+            }
+            for (column, cell) in row.into_iter().enumerate() {
+                let mut spans = header.get(column).cloned().unwrap_or_default();
+                if !spans.is_empty() {
+                    spans.push(Span::styled(": ", text_style(self.theme).add_modifier(Modifier::BOLD)));
+                }
+                spans.extend(cell);
+                for wrapped in wrap_spans(&spans, width) {
+                    let mut line = self.prefix();
+                    line.extend(wrapped);
+                    self.lines.push(Line::from(line));
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Line<'static>> {
+        if let Some(code) = self.code.take() {
+            // A code fence that is still streaming in
+            self.push_code(&code);
+        }
+        if let Some(table) = self.table.take() {
+            self.spans.clear();
+            self.push_table(table);
+        }
+        self.flush();
+        self.lines
+    }
+}
+
+fn spans_width(spans: &[Span]) -> usize {
+    spans.iter().map(Span::width).sum()
+}
+
+/// Cuts styled text to `width` columns, ending with a `…` marker if shortened.
+fn truncate_spans(spans: Vec<Span<'static>>, width: usize, marker: Style) -> Vec<Span<'static>> {
+    if spans_width(&spans) <= width {
+        return spans;
+    }
+    let mut result = Vec::new();
+    let mut used = 0;
+    for span in spans {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if used + char_width + 1 > width {
+                break;
+            }
+            text.push(c);
+            used += char_width;
+        }
+        let full = text.len() == span.content.len();
+        result.push(Span::styled(text, span.style));
+        if !full {
+            break;
+        }
+    }
+    result.push(Span::styled("…", marker));
+    result
+}
+
+#[derive(PartialEq)]
+enum WordKind {
+    Word,
+    Space,
+    Break,
+}
+
+struct Token {
+    kind: WordKind,
+    pieces: Vec<(String, Style)>,
+    width: usize,
+}
+
+/// Splits styled text into words, runs of spaces and hard breaks. A word
+/// may span several styles, as in `**bold**,`.
+fn tokenize(spans: &[Span<'static>]) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    for span in spans {
+        for c in span.content.chars() {
+            let kind = match c {
+                '\n' => WordKind::Break,
+                c if c.is_whitespace() => WordKind::Space,
+                _ => WordKind::Word,
+            };
+            let joins = kind != WordKind::Break
+                && tokens.last().is_some_and(|token| token.kind == kind);
+            if !joins {
+                tokens.push(Token { kind, pieces: Vec::new(), width: 0 });
+            }
+            if c == '\n' {
+                continue;
+            }
+            let token = tokens.last_mut().expect("token was just pushed");
+            token.width += c.width().unwrap_or(0);
+            push_piece(&mut token.pieces, c, span.style);
+        }
+    }
+    tokens
+}
+
+fn push_piece(pieces: &mut Vec<(String, Style)>, c: char, style: Style) {
+    match pieces.last_mut() {
+        Some((text, last)) if *last == style => text.push(c),
+        _ => pieces.push((c.to_string(), style)),
+    }
+}
+
+/// Greedily wraps styled text at word boundaries, splitting words that are
+/// wider than a whole line. Spaces inside a line are kept as written.
+fn wrap_spans(spans: &[Span<'static>], width: usize) -> Vec<Vec<Span<'static>>> {
+    let mut rows: Vec<Vec<(String, Style)>> = vec![Vec::new()];
+    let mut used = 0;
+    let mut space: Option<Token> = None;
+
+    for token in tokenize(spans) {
+        match token.kind {
+            WordKind::Break => {
+                rows.push(Vec::new());
+                used = 0;
+                space = None;
+            }
+            WordKind::Space => {
+                // Leading spaces of a wrapped line are dropped
+                if used > 0 {
+                    space = Some(token);
+                }
+            }
+            WordKind::Word => {
+                let space_width = space.as_ref().map_or(0, |space| space.width);
+                if used > 0 && used + space_width + token.width > width {
+                    rows.push(Vec::new());
+                    used = 0;
+                    space = None;
+                }
+                let row = rows.last_mut().expect("rows is never empty");
+                if let Some(space) = space.take() {
+                    row.extend(space.pieces);
+                    used += space_width;
+                }
+                if used + token.width <= width {
+                    row.extend(token.pieces);
+                    used += token.width;
+                    continue;
+                }
+                for (text, style) in token.pieces {
+                    for c in text.chars() {
+                        let char_width = c.width().unwrap_or(0);
+                        if used > 0 && used + char_width > width {
+                            rows.push(Vec::new());
+                            used = 0;
+                        }
+                        push_piece(rows.last_mut().expect("rows is never empty"), c, style);
+                        used += char_width;
+                    }
+                }
+            }
+        }
+    }
+
+    rows.into_iter()
+        .map(|row| row.into_iter().map(|(text, style)| Span::styled(text, style)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(text: &str, width: usize, selected_code: Option<usize>) -> Vec<Line<'static>> {
+        super::render(text, width, selected_code, &Theme::default())
+    }
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_block_structure() {
+        let text = "# Title\n\nSome text.\n\n- one\n- two\n  - nested\n\n1. first\n2. second\n\n> quoted\n\n---";
+        assert_eq!(
+            plain(&render(text, 40, None)),
+            vec![
+                "Title",
+                "",
+                "Some text.",
+                "",
+                "• one",
+                "• two",
+                "  ◦ nested",
+                "",
+                "1. first",
+                "2. second",
+                "",
+                "│ quoted",
+                "",
+                &"─".repeat(40),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrapping_keeps_indent_and_spacing() {
+        let lines = render("- alpha beta gamma\n\nkeep  two  spaces", 12, None);
+        assert_eq!(
+            plain(&lines),
+            vec!["• alpha beta", "  gamma", "", "keep  two", "spaces"]
+        );
+
+        let lines = render("abcdefghij", 4, None);
+        assert_eq!(plain(&lines), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_inline_styles() {
+        let lines = render("**bold**, *it* `code` [site](https://example.com)", 80, None);
+        let spans = &lines[0].spans;
+        assert_eq!(spans[0].content, "bold");
+        assert_eq!(spans[1].content, ",");
+        assert!(spans[0].style.add_modifier.contains(Modifier::BOLD));
+        assert!(!spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert!(spans.iter().any(|span| span.content == "it" && span.style.add_modifier.contains(Modifier::ITALIC)));
+        assert!(spans.iter().any(|span| span.content == "code" && span.style == code_style(&Theme::default())));
+        assert_eq!(plain(&lines), vec!["bold, it code site (https://example.com)"]);
+    }
+
+    #[test]
+    fn test_tables() {
+        let text = "| Lang | Speed |\n|:-----|------:|\n| Rust | fast |\n| Python | ok |";
+        assert_eq!(
+            plain(&render(text, 40, None)),
+            vec![
+                "┌────────┬───────┐",
+                "│ Lang   │ Speed │",
+                "├────────┼───────┤",
+                "│ Rust   │  fast │",
+                "│ Python │    ok │",
+                "└────────┴───────┘",
+            ]
+        );
+
+        // Too wide: the widest column gives way
+        assert_eq!(plain(&render(text, 17, None))[3], "│ Rust  │  fast │");
+        assert_eq!(plain(&render(text, 17, None))[4], "│ Pyth… │    ok │");
+
+        // Too narrow for columns at all
+        assert_eq!(
+            plain(&render(text, 12, None)),
+            vec!["Lang: Rust", "Speed: fast", "────────────", "Lang: Python", "Speed: ok"]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let text = "Run:\n\n```sh\ncargo run\n```\n\nthen\n\n    indented\n\n```py\nprint()";
+        assert_eq!(code_blocks(text), vec!["cargo run\n", "indented\n", "print()"]);
+    }
+
+    #[test]
+    fn test_code_block_keeps_indentation() {
+        let lines = render("```rust\nfn main() {\n    println!();\n}\n```", 40, None);
+        assert_eq!(
+            plain(&lines),
+            vec![" rust            ", " fn main() {     ", "     println!(); ", " }               "]
+        );
+        assert!(lines[1].spans.iter().all(|span| span.style.bg == Some(Theme::default().code_background)));
+        assert!(lines[1].spans.iter().any(|span| span.content == "fn" && span.style == token_style(TokenKind::Keyword, &Theme::default())));
+
+        let selected = render("```\nx\n```", 40, Some(0));
+        assert_eq!(plain(&selected)[0], " ▶ code ");
+
+        // Long lines are cut off rather than wrapped
+        let lines = render("```\nlet value = 1;\n```", 10, None);
+        assert_eq!(plain(&lines), vec![" let val… "]);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{commands::CommandSpec, keymap::Action};
+
+/// What choosing a palette entry does.
+#[derive(Debug, Clone, Copy)]
+pub enum PaletteItem {
+    Action(Action),
+    Command(&'static CommandSpec),
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub item: PaletteItem,
+    pub label: String,
+    /// Keys that trigger the entry directly, or the command to type.
+    pub shortcut: String,
+}
+
+/// The Ctrl+K popup: every action and command, narrowed by a fuzzy query.
+#[derive(Debug, Clone)]
+pub struct CommandPalette {
+    pub entries: Vec<PaletteEntry>,
+    pub query: String,
+    /// Index into [`CommandPalette::visible`].
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    pub fn new(entries: Vec<PaletteEntry>) -> Self {
+        Self { entries, query: String::new(), selected: 0 }
+    }
+
+    /// Entries matching the query, best match first.
+    pub fn visible(&self) -> Vec<&PaletteEntry> {
+        let mut scored: Vec<(i64, usize, &PaletteEntry)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let text = format!("{} {}", entry.label, entry.shortcut);
+                fuzzy_score(&self.query, &text).map(|score| (score, index, entry))
+            })
+            .collect();
+        // Ties keep the table order
+        scored.sort_by_key(|(score, index, _)| (std::cmp::Reverse(*score), *index));
+        scored.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+
+    pub fn selected_item(&self) -> Option<PaletteItem> {
+        self.visible().get(self.selected).map(|entry| entry.item)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.visible().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+}
+
+/// Scores `text` against `query` as an in-order subsequence, ignoring case
+/// and spaces in the query. Matches at word starts and runs of adjacent
+/// characters score higher; `None` if some query character is missing.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&index| text[index] == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Characters skipped over count a little against the match
+        score -= (found - position).min(5) as i64;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("", "anything").is_some());
+        assert!(fuzzy_score("xyz", "Switch model").is_none());
+        assert!(fuzzy_score("sw mo", "Switch model").is_some());
+
+        // Word starts and adjacent characters win
+        let model = fuzzy_score("model", "/model Switch model").unwrap();
+        let scattered = fuzzy_score("model", "Move to the oldest line").unwrap();
+        assert!(model > scattered);
+        let initials = fuzzy_score("cp", "Copy a message").unwrap();
+        let inner = fuzzy_score("cp", "Accept").unwrap();
+        assert!(initials > inner);
+    }
+}
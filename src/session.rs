@@ -0,0 +1,370 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config::get_data_dir, gemini::Role, ui::ChatMessage};
+
+/// A conversation as stored on disk, one JSON file per session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub title: String,
+    pub created: SystemTime,
+    pub updated: SystemTime,
+    #[serde(default)]
+    pub messages: Vec<ChatMessage>,
+}
+
+/// The directory conversations are saved in, one `<id>.json` file each.
+#[derive(Debug)]
+pub struct SessionStore {
+    dir: PathBuf,
+    /// Ids handed out this run; sessions only reach the disk once they
+    /// have messages.
+    issued: BTreeSet<String>,
+}
+
+impl SessionStore {
+    /// The `sessions` folder in the data directory.
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(get_data_dir()?.join("sessions")))
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir, issued: BTreeSet::new() }
+    }
+
+    /// A fresh, empty conversation with an id no saved one has.
+    pub fn new_session(&mut self) -> Session {
+        let now = SystemTime::now();
+        Session {
+            id: self.unique_id(now),
+            title: String::new(),
+            created: now,
+            updated: now,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Writes the session to disk. Empty conversations are not saved.
+    pub fn save(&self, session: &mut Session) -> Result<()> {
+        if session.messages.is_empty() {
+            return Ok(());
+        }
+
+        if session.title.is_empty() {
+            session.title = title_from(&session.messages);
+        }
+        session.updated = SystemTime::now();
+
+        let path = self.path(&session.id)?;
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create sessions directory: {:?}", self.dir))?;
+
+        let content = serde_json::to_string_pretty(session)
+            .with_context(|| "Failed to serialize session")?;
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write session file: {:?}", path))?;
+
+        Ok(())
+    }
+
+    pub fn load(&self, id: &str) -> Result<Session> {
+        let path = self.path(id)?;
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read session file: {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse session file: {:?}", path))
+    }
+
+    /// The most recently updated session, if any were saved.
+    pub fn load_latest(&self) -> Result<Option<Session>> {
+        match self.list()?.first() {
+            Some(summary) => self.load(&summary.id).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Every saved session, most recently updated first.
+    pub fn list(&self) -> Result<Vec<SessionSummary>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut sessions = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            // Skip files we cannot read rather than failing the whole list
+            let Ok(content) = fs::read_to_string(&path) else { continue };
+            let Ok(session) = serde_json::from_str::<Session>(&content) else { continue };
+            sessions.push(session.summary());
+        }
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.updated));
+        Ok(sessions)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<()> {
+        let path = self.path(id)?;
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to delete session file: {:?}", path))
+    }
+
+    pub fn rename(&self, id: &str, title: &str) -> Result<()> {
+        let mut session = self.load(id)?;
+        session.title = title.to_string();
+        self.save(&mut session)
+    }
+
+    /// [`session_id`] for `time`, followed by `-2`, `-3`, ... when a session
+    /// started earlier in the same second, in this run or on disk, has it.
+    fn unique_id(&mut self, time: SystemTime) -> String {
+        let base = session_id(time);
+        let id = std::iter::once(base.clone())
+            .chain((2..).map(|n| format!("{}-{}", base, n)))
+            .find(|id| !self.issued.contains(id) && !self.dir.join(format!("{}.json", id)).exists())
+            .expect("some suffix is free");
+        self.issued.insert(id.clone());
+        id
+    }
+
+    /// The file of session `id`. Ids come from the command line and `/load`,
+    /// so anything that could point outside the sessions directory is refused.
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+            anyhow::bail!("Invalid session id {:?}", id);
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
+
+impl Session {
+    /// The conversation as a Markdown document, one section per message.
+    /// Error notices are left out.
+    pub fn to_markdown(&self) -> String {
+        let title = if self.title.is_empty() { title_from(&self.messages) } else { self.title.clone() };
+        let mut markdown = format!("# {}\n", title);
+        for message in self.messages.iter().filter(|message| !message.is_error) {
+            let speaker = match (message.role, &message.persona) {
+                (Role::User, _) => "You".to_string(),
+                (Role::Model, Some(persona)) => format!("Gemini ({})", persona),
+                (Role::Model, None) => "Gemini".to_string(),
+            };
+            let status = if message.cancelled { " (cancelled)" } else { "" };
+            markdown.push_str(&format!("\n## {}{}\n\n{}\n", speaker, status, message.content.trim_end()));
+        }
+        markdown
+    }
+
+    /// Where [`Session::export`] writes to: `path`, or a file named after
+    /// the session in the exports directory when no path is given.
+    pub fn export_path(&self, path: Option<&str>) -> Result<PathBuf> {
+        match path {
+            Some(path) => Ok(PathBuf::from(path)),
+            None => {
+                let dir = get_data_dir()?.join("exports");
+                fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create exports directory: {:?}", dir))?;
+                Ok(dir.join(format!("{}.md", self.id)))
+            }
+        }
+    }
+
+    /// Writes [`Session::to_markdown`] to `path`. An existing file is only
+    /// replaced when `overwrite` is set.
+    pub fn export(&self, path: &Path, overwrite: bool) -> Result<()> {
+        let mut file = if overwrite {
+            File::create(path)
+        } else {
+            OpenOptions::new().write(true).create_new(true).open(path)
+        }
+        .with_context(|| format!("Failed to create export file: {:?}", path))?;
+        file.write_all(self.to_markdown().as_bytes())
+            .with_context(|| format!("Failed to write export file: {:?}", path))
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            updated: self.updated,
+            message_count: self.messages.len(),
+        }
+    }
+}
+
+/// What the conversation browser needs to list a session.
+#[derive(Debug, Clone)]
+pub struct SessionSummary {
+    pub id: String,
+    pub title: String,
+    pub updated: SystemTime,
+    pub message_count: usize,
+}
+
+/// The first user message, shortened to fit on one line.
+fn title_from(messages: &[ChatMessage]) -> String {
+    let first = messages
+        .iter()
+        .find(|message| message.role == Role::User)
+        .map(|message| message.content.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+
+    if first.chars().count() > 50 {
+        format!("{}…", first.chars().take(49).collect::<String>())
+    } else if first.is_empty() {
+        "Untitled".to_string()
+    } else {
+        first
+    }
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC, which also sorts chronologically.
+fn session_id(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        (secs / 3600) % 24,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+/// Converts days since 1970-01-01 into a (year, month, day) date.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    /// A store in a scratch directory of its own.
+    fn store() -> SessionStore {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        SessionStore::at(std::env::temp_dir().join(format!("gemini-chat-tui-sessions-{}-{}", std::process::id(), n)))
+    }
+
+    #[test]
+    fn test_session_id_is_a_utc_timestamp() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_792_243_845);
+        assert_eq!(session_id(time), "20261017-133045");
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_sessions_started_together_get_their_own_ids() {
+        let mut store = store();
+        let time = UNIX_EPOCH + Duration::from_secs(1_792_243_845);
+        assert_eq!(store.unique_id(time), "20261017-133045");
+        assert_eq!(store.unique_id(time), "20261017-133045-2");
+        assert_ne!(store.new_session().id, store.new_session().id);
+
+        // Ids saved by an earlier run are taken too
+        let mut saved = store.new_session();
+        saved.id = "20261017-133045".to_string();
+        saved.messages.push(ChatMessage {
+            content: "hi".to_string(),
+            role: Role::User,
+            is_error: false,
+            cancelled: false,
+            persona: None,
+            model: None,
+            timestamp: UNIX_EPOCH,
+        });
+        store.save(&mut saved).unwrap();
+        assert_eq!(SessionStore::at(store.dir.clone()).unique_id(time), "20261017-133045-2");
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_ids_stay_inside_the_sessions_directory() {
+        let store = store();
+        for id in ["../config", "a/b", "a\\b", "..", ""] {
+            let error = store.load(id).unwrap_err().to_string();
+            assert_eq!(error, format!("Invalid session id {:?}", id));
+            assert!(store.delete(id).is_err());
+        }
+        assert_eq!(store.path("20261017-133045-2").unwrap(), store.dir.join("20261017-133045-2.json"));
+    }
+
+    #[test]
+    fn test_export_does_not_replace_a_file_unasked() {
+        let path = std::env::temp_dir().join(format!("gemini-chat-tui-export-{}.md", std::process::id()));
+        fs::write(&path, "notes").unwrap();
+
+        let session = store().new_session();
+        assert!(session.export(&path, false).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "notes");
+        session.export(&path, true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Untitled\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_session_round_trips_through_json() {
+        let mut session = store().new_session();
+        session.messages.push(ChatMessage {
+            content: "What is a **lifetime**?".to_string(),
+            role: Role::User,
+            is_error: false,
+            cancelled: false,
+            persona: None,
+            model: None,
+            timestamp: UNIX_EPOCH + Duration::from_secs(1_000),
+        });
+        session.messages.push(ChatMessage {
+            content: "A lifetime is...".to_string(),
+            role: Role::Model,
+            is_error: false,
+            cancelled: false,
+            persona: Some("reviewer".to_string()),
+            model: Some("gemini-2.0-flash".to_string()),
+            timestamp: UNIX_EPOCH + Duration::from_secs(1_005),
+        });
+
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.id, session.id);
+        assert_eq!(restored.messages.len(), 2);
+        assert_eq!(restored.messages[1].role, Role::Model);
+        assert_eq!(restored.messages[1].model.as_deref(), Some("gemini-2.0-flash"));
+        assert_eq!(restored.messages[1].timestamp, session.messages[1].timestamp);
+        assert_eq!(title_from(&restored.messages), "What is a **lifetime**?");
+
+        assert_eq!(
+            restored.to_markdown(),
+            "# What is a **lifetime**?\n\n## You\n\nWhat is a **lifetime**?\n\n## Gemini (reviewer)\n\nA lifetime is...\n"
+        );
+    }
+}
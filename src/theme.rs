@@ -0,0 +1,412 @@
+use anyhow::{bail, Context, Result};
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{fs, path::PathBuf};
+
+use crate::config::get_config_dir;
+
+/// Names of the built-in themes, the first being the default.
+pub const PRESETS: [&str; 5] = ["dark", "light", "high-contrast", "solarized", "monochrome"];
+
+/// Every color the UI draws with, by role.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    /// Behind every panel.
+    pub background: Color,
+    pub text: Color,
+    /// Labels and hints.
+    pub secondary: Color,
+    /// Timestamps, placeholders and other details.
+    pub muted: Color,
+    /// Border of the chat panel.
+    pub border: Color,
+    /// Borders of the input box and popups, and headings in them.
+    pub accent: Color,
+    /// Background of the selected row in lists.
+    pub highlight: Color,
+    /// Keys and commands in the help and the palette.
+    pub key: Color,
+    /// Your messages.
+    pub user: Color,
+    /// Gemini's replies.
+    pub model: Color,
+    /// A message picked for copying.
+    pub selected: Color,
+    /// Waiting for Gemini.
+    pub busy: Color,
+    /// The status bar while idle.
+    pub ok: Color,
+    pub error: Color,
+    /// The title cycles through these.
+    pub title: Vec<Color>,
+    pub vim_normal: Color,
+    pub vim_insert: Color,
+    pub vim_visual: Color,
+    /// Inline code.
+    pub code: Color,
+    /// Behind code blocks.
+    pub code_background: Color,
+    pub keyword: Color,
+    /// Numbers and constants such as `true`.
+    pub literal: Color,
+    pub string: Color,
+    pub comment: Color,
+    pub function: Color,
+    pub heading: Color,
+    pub subheading: Color,
+    pub link: Color,
+    pub list_marker: Color,
+    /// Leaves colors to the terminal and tells things apart with bold,
+    /// underline and reverse video instead.
+    #[serde(skip)]
+    pub monochrome: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// The look the app always had: bright colors on black.
+    fn dark() -> Self {
+        Self {
+            background: Color::Black,
+            text: Color::White,
+            secondary: Color::Gray,
+            muted: Color::DarkGray,
+            border: Color::White,
+            accent: Color::Magenta,
+            highlight: Color::DarkGray,
+            key: Color::Cyan,
+            user: Color::Cyan,
+            model: Color::Green,
+            selected: Color::Yellow,
+            busy: Color::Yellow,
+            ok: Color::Green,
+            error: Color::Red,
+            title: vec![Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::Blue, Color::Magenta],
+            vim_normal: Color::Blue,
+            vim_insert: Color::Green,
+            vim_visual: Color::Magenta,
+            code: Color::Yellow,
+            code_background: Color::Indexed(236),
+            keyword: Color::LightMagenta,
+            literal: Color::LightCyan,
+            string: Color::LightGreen,
+            comment: Color::DarkGray,
+            function: Color::LightBlue,
+            heading: Color::Magenta,
+            subheading: Color::Cyan,
+            link: Color::LightBlue,
+            list_marker: Color::Cyan,
+            monochrome: false,
+        }
+    }
+
+    /// Dark text on white, in exact colors so the terminal palette cannot
+    /// wash it out.
+    fn light() -> Self {
+        let blue = Color::Rgb(9, 105, 218);
+        let green = Color::Rgb(26, 127, 55);
+        let purple = Color::Rgb(130, 80, 223);
+        Self {
+            background: Color::Rgb(255, 255, 255),
+            text: Color::Rgb(36, 41, 47),
+            secondary: Color::Rgb(87, 96, 106),
+            muted: Color::Rgb(140, 149, 159),
+            border: Color::Rgb(87, 96, 106),
+            accent: purple,
+            highlight: Color::Rgb(221, 228, 235),
+            key: blue,
+            user: blue,
+            model: green,
+            selected: Color::Rgb(191, 135, 0),
+            busy: Color::Rgb(154, 103, 0),
+            ok: green,
+            error: Color::Rgb(207, 34, 46),
+            title: vec![
+                Color::Rgb(207, 34, 46),
+                Color::Rgb(188, 76, 0),
+                Color::Rgb(154, 103, 0),
+                green,
+                blue,
+                purple,
+            ],
+            vim_normal: blue,
+            vim_insert: green,
+            vim_visual: purple,
+            code: Color::Rgb(149, 56, 0),
+            code_background: Color::Rgb(240, 243, 246),
+            keyword: Color::Rgb(207, 34, 46),
+            literal: Color::Rgb(5, 80, 174),
+            string: Color::Rgb(10, 48, 105),
+            comment: Color::Rgb(110, 119, 129),
+            function: purple,
+            heading: purple,
+            subheading: blue,
+            link: blue,
+            list_marker: blue,
+            monochrome: false,
+        }
+    }
+
+    /// Only the brightest colors on black, nothing dim.
+    fn high_contrast() -> Self {
+        Self {
+            background: Color::Black,
+            text: Color::White,
+            secondary: Color::White,
+            muted: Color::Gray,
+            border: Color::White,
+            accent: Color::LightYellow,
+            highlight: Color::Blue,
+            key: Color::LightCyan,
+            user: Color::LightCyan,
+            model: Color::LightGreen,
+            selected: Color::LightYellow,
+            busy: Color::LightYellow,
+            ok: Color::LightGreen,
+            error: Color::LightRed,
+            title: vec![Color::White],
+            vim_normal: Color::LightCyan,
+            vim_insert: Color::LightGreen,
+            vim_visual: Color::LightMagenta,
+            code: Color::LightYellow,
+            code_background: Color::Black,
+            keyword: Color::LightMagenta,
+            literal: Color::LightCyan,
+            string: Color::LightGreen,
+            comment: Color::Gray,
+            function: Color::LightYellow,
+            heading: Color::LightYellow,
+            subheading: Color::LightCyan,
+            link: Color::LightCyan,
+            list_marker: Color::LightYellow,
+            monochrome: false,
+        }
+    }
+
+    /// Ethan Schoonover's Solarized, dark variant.
+    fn solarized() -> Self {
+        let yellow = Color::Rgb(181, 137, 0);
+        let orange = Color::Rgb(203, 75, 22);
+        let red = Color::Rgb(220, 50, 47);
+        let magenta = Color::Rgb(211, 54, 130);
+        let violet = Color::Rgb(108, 113, 196);
+        let blue = Color::Rgb(38, 139, 210);
+        let cyan = Color::Rgb(42, 161, 152);
+        let green = Color::Rgb(133, 153, 0);
+        let base02 = Color::Rgb(7, 54, 66);
+        let base01 = Color::Rgb(88, 110, 117);
+        Self {
+            background: Color::Rgb(0, 43, 54),
+            text: Color::Rgb(147, 161, 161),
+            secondary: Color::Rgb(131, 148, 150),
+            muted: base01,
+            border: Color::Rgb(131, 148, 150),
+            accent: violet,
+            highlight: base02,
+            key: cyan,
+            user: blue,
+            model: green,
+            selected: yellow,
+            busy: yellow,
+            ok: green,
+            error: red,
+            title: vec![red, orange, yellow, green, cyan, blue, violet, magenta],
+            vim_normal: blue,
+            vim_insert: green,
+            vim_visual: magenta,
+            code: orange,
+            code_background: base02,
+            keyword: green,
+            literal: magenta,
+            string: cyan,
+            comment: base01,
+            function: blue,
+            heading: orange,
+            subheading: yellow,
+            link: blue,
+            list_marker: cyan,
+            monochrome: false,
+        }
+    }
+
+    /// No colors at all, for `NO_COLOR` and terminals without them.
+    fn monochrome() -> Self {
+        let none = Color::Reset;
+        Self {
+            background: none,
+            text: none,
+            secondary: none,
+            muted: none,
+            border: none,
+            accent: none,
+            highlight: none,
+            key: none,
+            user: none,
+            model: none,
+            selected: none,
+            busy: none,
+            ok: none,
+            error: none,
+            title: vec![none],
+            vim_normal: none,
+            vim_insert: none,
+            vim_visual: none,
+            code: none,
+            code_background: none,
+            keyword: none,
+            literal: none,
+            string: none,
+            comment: none,
+            function: none,
+            heading: none,
+            subheading: none,
+            link: none,
+            list_marker: none,
+            monochrome: true,
+        }
+    }
+
+    /// A built-in theme by name.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "solarized" => Some(Self::solarized()),
+            "monochrome" => Some(Self::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// A built-in theme, or the theme file `themes/NAME.json` next to the
+    /// config file.
+    pub fn load(name: &str) -> Result<Self> {
+        if let Some(theme) = Self::preset(name) {
+            return Ok(theme);
+        }
+        let path = themes_dir()?.join(format!("{}.json", name));
+        if !path.exists() {
+            bail!("Unknown theme {:?} (available: {})", name, Self::names().join(", "));
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read theme file: {:?}", path))?;
+        Self::parse(&content).with_context(|| format!("Failed to parse theme file: {:?}", path))
+    }
+
+    /// A theme file: the colors that differ from the preset named by
+    /// `base`, or from `dark` without one.
+    fn parse(content: &str) -> Result<Self> {
+        let mut colors: Map<String, Value> = serde_json::from_str(content)?;
+        let base = match colors.remove("base") {
+            Some(Value::String(base)) => {
+                Self::preset(&base).with_context(|| format!("Unknown base theme {:?}", base))?
+            }
+            Some(_) => bail!("\"base\" must name a built-in theme"),
+            None => Self::default(),
+        };
+        base.with_colors(&colors)
+    }
+
+    /// This theme with some roles recolored, e.g. `"user": "#268bd2"`.
+    pub fn with_colors(&self, colors: &Map<String, Value>) -> Result<Self> {
+        let mut fields = match serde_json::to_value(self)? {
+            Value::Object(fields) => fields,
+            _ => unreachable!("a theme serializes to an object"),
+        };
+        for (role, color) in colors {
+            if !fields.contains_key(role) {
+                let known: Vec<&str> = fields.keys().map(String::as_str).collect();
+                bail!("Unknown theme color {:?} (available: {})", role, known.join(", "));
+            }
+            fields.insert(role.clone(), color.clone());
+        }
+        let theme: Self = serde_json::from_value(Value::Object(fields)).context("Invalid color")?;
+        Ok(Self { monochrome: self.monochrome, ..theme })
+    }
+
+    /// The selected row of a list.
+    pub fn highlight_style(&self) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().bg(self.highlight)
+        }
+    }
+
+    /// Text set on a patch of `color`, like the Vim mode badge.
+    pub fn badge_style(&self, color: Color) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(self.background).bg(color)
+        }
+    }
+
+    /// The built-in themes followed by the saved theme files.
+    pub fn names() -> Vec<String> {
+        let mut names: Vec<String> = PRESETS.iter().map(|name| name.to_string()).collect();
+        let files = themes_dir().ok().and_then(|dir| fs::read_dir(dir).ok());
+        let mut custom: Vec<String> = files
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            })
+            .filter(|name| !names.contains(name))
+            .collect();
+        custom.sort();
+        names.extend(custom);
+        names
+    }
+}
+
+fn themes_dir() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_round_trip() {
+        for name in PRESETS {
+            let theme = Theme::preset(name).unwrap();
+            let json = serde_json::to_string(&theme).unwrap();
+            let parsed = serde_json::from_str::<Theme>(&json).unwrap();
+            assert_eq!(Theme { monochrome: theme.monochrome, ..parsed }, theme, "{}", name);
+        }
+        assert_eq!(Theme::default(), Theme::preset("dark").unwrap());
+        assert!(Theme::preset("sepia").is_none());
+
+        // Recoloring keeps the monochrome flag the files cannot set
+        let monochrome = Theme::preset("monochrome").unwrap();
+        assert!(monochrome.with_colors(&Map::new()).unwrap().monochrome);
+        assert_eq!(monochrome.highlight_style(), Style::default().add_modifier(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_theme_files() {
+        let theme = Theme::parse(r##"{ "base": "light", "user": "#268bd2", "title": ["red", "blue"] }"##).unwrap();
+        assert_eq!(theme.user, Color::Rgb(38, 139, 210));
+        assert_eq!(theme.title, vec![Color::Red, Color::Blue]);
+        assert_eq!(theme.background, Theme::light().background);
+        assert_eq!(Theme::parse(r#"{ "code_background": "235" }"#).unwrap().code_background, Color::Indexed(235));
+
+        let error = |content: &str| format!("{:#}", Theme::parse(content).unwrap_err());
+        assert!(error(r#"{ "base": "sepia" }"#).starts_with("Unknown base theme \"sepia\""));
+        assert!(error(r#"{ "usr": "red" }"#).starts_with("Unknown theme color \"usr\""));
+        assert!(error(r#"{ "user": "reddish" }"#).starts_with("Invalid color"));
+    }
+}
@@ -1,84 +1,410 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Block, Borders, List, ListItem, Paragraph, Wrap,
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Wrap,
     },
     Frame,
 };
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    ops::Range,
+};
 use unicode_width::{UnicodeWidthStr, UnicodeWidthChar};
 
-#[derive(Debug, Clone)]
+use crate::{
+    editor::TextArea,
+    gemini::{Content, GenerationConfig, ModelInfo, Role},
+    commands::{self, COMMANDS},
+    history::HistorySearch,
+    keymap::{self, Action, Context, KeyChord},
+    markdown,
+    palette::CommandPalette,
+    session::{civil_from_days, SessionSummary},
+    theme::Theme,
+    vim::{self, Mode, Vim},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub content: String,
-    pub is_user: bool,
+    pub role: Role,
+    /// Error notices are shown in the chat but never sent back to Gemini.
+    #[serde(default)]
+    pub is_error: bool,
+    /// The request for this turn was cancelled; also never sent to Gemini.
+    #[serde(default)]
+    pub cancelled: bool,
+    /// The persona that was active when Gemini wrote this reply.
+    #[serde(default)]
+    pub persona: Option<String>,
+    /// The model that wrote this reply.
+    #[serde(default)]
+    pub model: Option<String>,
     pub timestamp: std::time::SystemTime,
 }
 
+/// Popup listing the models returned by `models.list`.
+#[derive(Debug, Clone, Default)]
+pub struct ModelPicker {
+    /// `None` while the list is still being fetched.
+    pub models: Option<Vec<ModelInfo>>,
+    pub selected: usize,
+    /// The `models.list` request filling this picker; replies to requests
+    /// from a picker closed since are dropped.
+    pub request_id: u64,
+}
+
+impl ModelPicker {
+    pub fn select_next(&mut self) {
+        let len = self.models.as_ref().map_or(0, Vec::len);
+        if self.selected + 1 < len {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_model(&self) -> Option<&ModelInfo> {
+        self.models.as_ref()?.get(self.selected)
+    }
+}
+
+/// The F1 overlay listing every key binding and slash command.
+#[derive(Debug, Clone)]
+pub struct HelpOverlay {
+    /// Each action with its keys, straight from the keymap in use.
+    pub bindings: Vec<(Action, Vec<KeyChord>)>,
+    /// Also list the Vim keys of the input box.
+    pub vim: bool,
+    pub scroll: usize,
+    /// Furthest the list can scroll, as of the last frame.
+    pub max_scroll: Cell<usize>,
+}
+
+impl HelpOverlay {
+    pub fn new(bindings: Vec<(Action, Vec<KeyChord>)>, vim: bool) -> Self {
+        Self { bindings, vim, scroll: 0, max_scroll: Cell::new(0) }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll = (self.scroll + 1).min(self.max_scroll.get());
+    }
+}
+
+/// What keys typed into the conversation browser currently do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SidebarMode {
+    Browse,
+    Filter,
+    Rename(String),
+    ConfirmDelete,
+}
+
+/// The conversation browser shown to the left of the chat.
+#[derive(Debug, Clone)]
+pub struct Sidebar {
+    pub sessions: Vec<SessionSummary>,
+    /// Index into [`Sidebar::visible`].
+    pub selected: usize,
+    pub filter: String,
+    pub mode: SidebarMode,
+    /// Whether keys go to the sidebar rather than the input box.
+    pub focused: bool,
+}
+
+impl Sidebar {
+    pub fn new(sessions: Vec<SessionSummary>) -> Self {
+        Self {
+            sessions,
+            selected: 0,
+            filter: String::new(),
+            mode: SidebarMode::Browse,
+            focused: true,
+        }
+    }
+
+    /// Sessions whose title matches the filter, case-insensitively.
+    pub fn visible(&self) -> Vec<&SessionSummary> {
+        let filter = self.filter.to_lowercase();
+        self.sessions
+            .iter()
+            .filter(|session| session.title.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    pub fn selected_session(&self) -> Option<&SessionSummary> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.visible().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn set_sessions(&mut self, sessions: Vec<SessionSummary>) {
+        self.sessions = sessions;
+        self.clamp_selection();
+    }
+
+    /// Keeps the selection on an existing row after the list shrank.
+    pub fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.visible().len().saturating_sub(1));
+    }
+}
+
+/// A message, or one code block inside it, picked out for copying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChatSelection {
+    pub message: usize,
+    /// Index of a code block within the message; `None` means all of it.
+    pub code_block: Option<usize>,
+}
+
+/// Size of the chat area as of the last frame, recorded while rendering.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChatViewport {
+    pub height: usize,
+    pub max_scroll: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub messages: Vec<ChatMessage>,
-    pub input: String,
-    pub input_cursor: usize,
+    pub input: TextArea,
+    /// Ctrl+R search through sent prompts, shown in place of the input.
+    pub history_search: Option<HistorySearch>,
+    /// First visible chat line when not following the newest messages.
     pub scroll_offset: usize,
+    /// Keep the newest message in view as the chat grows.
+    pub follow_bottom: bool,
+    /// Something arrived while the user was reading older messages.
+    pub unseen_messages: bool,
+    pub chat_viewport: Cell<ChatViewport>,
+    /// Chat lines each message occupied in the last frame.
+    pub message_rows: RefCell<Vec<Range<usize>>>,
+    /// The F1 / `/help` overlay, when open.
+    pub help: Option<HelpOverlay>,
+    /// The Ctrl+K command palette, drawn over the chat.
+    pub palette: Option<CommandPalette>,
+    /// Vim-style modal editing of the input, when enabled in the config.
+    pub vim: Option<Vim>,
+    /// Shown in the empty input box, naming the keys in use.
+    pub input_placeholder: String,
+    /// Colors to draw with; `/theme` swaps it while running.
+    pub theme: Theme,
+    /// Keep the title and the spinner still.
+    pub reduced_motion: bool,
+    /// Leave out the title row and the margin around the screen.
+    pub compact: bool,
+    /// Set while picking a message or code block to copy.
+    pub selection: Option<ChatSelection>,
     pub is_loading: bool,
+    /// A streamed reply is being appended to the last message.
+    pub streaming: bool,
     pub status_message: String,
     pub animation_frame: usize,
+    /// The model new messages are sent to.
+    pub model: String,
+    /// Sampling parameters sent with each request.
+    pub generation: GenerationConfig,
+    /// Name of the active persona, if any.
+    pub persona: Option<String>,
+    pub model_picker: Option<ModelPicker>,
+    pub sidebar: Option<Sidebar>,
+    /// Id of the session the chat is saved to, marked in the sidebar.
+    pub session_id: String,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             messages: Vec::new(),
-            input: String::new(),
-            input_cursor: 0,
+            input: TextArea::default(),
+            history_search: None,
             scroll_offset: 0,
+            follow_bottom: true,
+            unseen_messages: false,
+            chat_viewport: Cell::new(ChatViewport::default()),
+            message_rows: RefCell::new(Vec::new()),
+            vim: None,
+            input_placeholder: "Type your message here... (Enter to send, Shift+Enter for a new line, F1 for help)".to_string(),
+            theme: Theme::default(),
+            reduced_motion: false,
+            compact: false,
+            selection: None,
+            help: None,
+            palette: None,
             is_loading: false,
+            streaming: false,
             status_message: "Ready to chat with Gemini! 🚀".to_string(),
             animation_frame: 0,
+            model: crate::gemini::DEFAULT_MODEL.to_string(),
+            generation: GenerationConfig::default(),
+            persona: None,
+            model_picker: None,
+            sidebar: None,
+            session_id: String::new(),
         }
     }
 }
 
 impl AppState {
-    pub fn add_message(&mut self, content: String, is_user: bool) {
-        self.messages.push(ChatMessage {
+    pub fn add_message(&mut self, content: String, role: Role) {
+        let (persona, model) = match role {
+            Role::Model => (self.persona.clone(), Some(self.model.clone())),
+            Role::User => (None, None),
+        };
+        self.push_message(ChatMessage {
             content,
-            is_user,
+            role,
+            is_error: false,
+            cancelled: false,
+            persona,
+            model,
             timestamp: std::time::SystemTime::now(),
         });
-        // Auto-scroll to bottom
-        self.scroll_offset = self.messages.len().saturating_sub(1);
     }
 
-    pub fn insert_char(&mut self, c: char) {
-        self.input.insert(self.input_cursor, c);
-        self.input_cursor += 1;
+    pub fn add_error(&mut self, content: String) {
+        self.push_message(ChatMessage {
+            content,
+            role: Role::Model,
+            is_error: true,
+            cancelled: false,
+            persona: None,
+            model: None,
+            timestamp: std::time::SystemTime::now(),
+        });
     }
 
-    pub fn delete_char(&mut self) {
-        if self.input_cursor > 0 {
-            self.input.remove(self.input_cursor - 1);
-            self.input_cursor -= 1;
+    fn push_message(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+        if !self.follow_bottom {
+            self.unseen_messages = true;
         }
     }
 
-    pub fn move_cursor_left(&mut self) {
-        if self.input_cursor > 0 {
-            self.input_cursor -= 1;
+    pub fn scroll_up(&mut self, lines: usize) {
+        let viewport = self.chat_viewport.get();
+        if self.follow_bottom {
+            self.scroll_offset = viewport.max_scroll;
+        }
+        self.scroll_offset = self.scroll_offset.min(viewport.max_scroll).saturating_sub(lines);
+        self.follow_bottom = viewport.max_scroll == 0;
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        if self.follow_bottom {
+            return;
+        }
+        self.scroll_offset += lines;
+        if self.scroll_offset >= self.chat_viewport.get().max_scroll {
+            self.scroll_to_bottom();
+        }
+    }
+
+    pub fn scroll_page_up(&mut self) {
+        self.scroll_up(self.chat_viewport.get().height.saturating_sub(2).max(1));
+    }
+
+    pub fn scroll_page_down(&mut self) {
+        self.scroll_down(self.chat_viewport.get().height.saturating_sub(2).max(1));
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll_offset = 0;
+        self.follow_bottom = self.chat_viewport.get().max_scroll == 0;
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.follow_bottom = true;
+        self.unseen_messages = false;
+    }
+
+    /// Selects for copying and scrolls just far enough to show the message.
+    pub fn select(&mut self, selection: ChatSelection) {
+        self.selection = Some(selection);
+        let Some(rows) = self.message_rows.borrow().get(selection.message).cloned() else {
+            return;
+        };
+        let viewport = self.chat_viewport.get();
+        let top = if self.follow_bottom { viewport.max_scroll } else { self.scroll_offset.min(viewport.max_scroll) };
+        let top = if rows.start < top {
+            rows.start
+        } else if rows.end > top + viewport.height {
+            rows.end.saturating_sub(viewport.height).min(rows.start)
+        } else {
+            top
+        };
+        self.scroll_offset = top;
+        self.follow_bottom = top >= viewport.max_scroll;
+    }
+
+    /// Grows the Gemini bubble of the reply currently being streamed,
+    /// starting a new one for the first chunk.
+    pub fn append_stream_chunk(&mut self, chunk: &str) {
+        match self.messages.last_mut() {
+            Some(message) if self.streaming => {
+                message.content.push_str(chunk);
+                if !self.follow_bottom {
+                    self.unseen_messages = true;
+                }
+            }
+            _ => {
+                self.add_message(chunk.to_string(), Role::Model);
+                self.streaming = true;
+            }
         }
     }
 
-    pub fn move_cursor_right(&mut self) {
-        if self.input_cursor < self.input.len() {
-            self.input_cursor += 1;
+    pub fn finish_stream(&mut self) {
+        self.streaming = false;
+    }
+
+    /// Marks the unanswered user turn (and any partially streamed reply)
+    /// as cancelled so it stays visible but drops out of the context.
+    pub fn cancel_pending_turn(&mut self) {
+        if self.streaming {
+            if let Some(reply) = self.messages.last_mut() {
+                reply.cancelled = true;
+            }
         }
+        if let Some(turn) = self.messages.iter_mut().rev().find(|m| m.role == Role::User) {
+            turn.cancelled = true;
+        }
+        self.streaming = false;
+        self.is_loading = false;
     }
 
-    pub fn clear_input(&mut self) {
-        self.input.clear();
-        self.input_cursor = 0;
+    /// The transcript in the shape Gemini expects, so follow-up questions
+    /// keep the earlier turns as context. Prompts whose request failed are
+    /// left out with their error, keeping user and model turns alternating.
+    pub fn conversation(&self) -> Vec<Content> {
+        let failed = |index: usize| self.messages.get(index + 1).is_some_and(|next| next.is_error);
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(index, message)| {
+                let unanswered = message.role == Role::User && failed(*index);
+                !message.is_error && !message.cancelled && !unanswered
+            })
+            .map(|(_, message)| Content::new(message.role, message.content.clone()))
+            .collect()
     }
 
     pub fn increment_animation(&mut self) {
@@ -86,40 +412,378 @@ impl AppState {
     }
 }
 
+/// The input box grows with its content up to this many rows.
+const MAX_INPUT_ROWS: usize = 8;
+
 pub fn ui(f: &mut Frame, app: &AppState) {
+    let theme = &app.theme;
+    // Paint the whole screen so light themes work on dark terminals too
+    f.render_widget(Block::default().style(Style::default().fg(theme.text).bg(theme.background)), f.area());
+
+    let margin = if app.compact { 0 } else { 1 };
+    // Inside the outer margin and the input box borders
+    let input_width = f.area().width.saturating_sub(2 * margin + 2) as usize;
+    let input_rows = app.input.wrapped(input_width).0.len().clamp(1, MAX_INPUT_ROWS);
+    let hint = match app.history_search {
+        Some(_) => None,
+        None => commands::hint(&app.input.text()),
+    };
+    
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .margin(1)
+        .margin(margin)
         .constraints([
-            Constraint::Length(3),   // Title
+            Constraint::Length(if app.compact { 0 } else { 3 }),   // Title
             Constraint::Min(0),      // Chat area
-            Constraint::Length(3),   // Input
+            Constraint::Length(u16::from(hint.is_some())),   // Command hint
+            Constraint::Length(input_rows as u16 + 2),   // Input
             Constraint::Length(3),   // Status
         ])
         .split(f.area());
 
     // Crazy animated title
-    render_title(f, chunks[0], app.animation_frame);
+    if !app.compact {
+        render_title(f, chunks[0], app.animation_frame, theme);
+    }
 
-    // Chat messages area
-    render_chat_area(f, chunks[1], app);
+    // Chat messages area, with the conversation browser to its left
+    let chat_area = match &app.sidebar {
+        Some(sidebar) => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(34), Constraint::Min(0)])
+                .split(chunks[1]);
+            render_sidebar(f, columns[0], sidebar, &app.session_id, theme);
+            columns[1]
+        }
+        None => chunks[1],
+    };
+    render_chat_area(f, chat_area, app);
+
+    // Usage of the slash command being typed
+    if let Some(hint) = hint {
+        let hint = truncate_to_width(&hint, chunks[2].width.saturating_sub(1) as usize);
+        f.render_widget(
+            Paragraph::new(format!(" {}", hint)).style(Style::default().fg(theme.secondary)),
+            chunks[2],
+        );
+    }
 
     // Input area
-    render_input_area(f, chunks[2], app);
+    render_input_area(f, chunks[3], app);
 
     // Status bar
-    render_status_bar(f, chunks[3], app);
+    render_status_bar(f, chunks[4], app);
+
+    // Popups go last so they draw over everything else
+    if let Some(picker) = &app.model_picker {
+        render_model_picker(f, picker, &app.model, theme);
+    }
+    if let Some(help) = &app.help {
+        render_help(f, help, theme);
+    }
+    if let Some(palette) = &app.palette {
+        render_palette(f, chat_area, palette, theme);
+    }
+}
+
+/// The Ctrl+K palette, anchored to the top of the chat area.
+fn render_palette(f: &mut Frame, chat_area: Rect, palette: &CommandPalette, theme: &Theme) {
+    let width = chat_area.width.saturating_sub(4).min(90);
+    let area = Rect {
+        x: chat_area.x + (chat_area.width - width) / 2,
+        y: chat_area.y + 1,
+        width,
+        height: chat_area.height.saturating_sub(2).min(18),
+    };
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" > {}▏", palette.query))
+        .title_bottom(" ↑/↓ choose · Enter run · Esc close ")
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.background));
+
+    // Label on the left, shortcut right-aligned
+    let inner_width = width.saturating_sub(4) as usize;
+    let items: Vec<ListItem> = palette
+        .visible()
+        .into_iter()
+        .map(|entry| {
+            let shortcut_width = entry.shortcut.width();
+            let label = truncate_to_width(&entry.label, inner_width.saturating_sub(shortcut_width + 2));
+            let gap = inner_width.saturating_sub(label.width() + shortcut_width);
+            ListItem::new(Line::from(vec![
+                Span::styled(label, Style::default().fg(theme.text)),
+                Span::raw(" ".repeat(gap)),
+                Span::styled(entry.shortcut.clone(), Style::default().fg(theme.key)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+
+    let mut list_state = ListState::default().with_selected(Some(palette.selected));
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+/// Every key binding grouped by where it applies, then every slash command
+/// with its usage and aliases.
+fn render_help(f: &mut Frame, help: &HelpOverlay, theme: &Theme) {
+    let area = centered_rect(90, 80, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Keys and commands ")
+        .title_bottom(" ↑/↓ scroll · Esc close ")
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.background));
+
+    let heading = |title: &str| {
+        Line::from(Span::styled(title.to_string(), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)))
+    };
+    // Plain characters never reach a global action from the input box, so
+    // they are listed apart from the keys that work anywhere
+    let split = |action: &Action, chords: &[KeyChord]| -> (Vec<KeyChord>, Vec<KeyChord>) {
+        chords.iter().partition(|chord| !(action.context() == Context::Global && chord.is_text()))
+    };
+    let keys = |chords: &[KeyChord]| match chords {
+        [] => "unbound".to_string(),
+        chords => keymap::describe(chords),
+    };
+    // A long list of keys pushes its own label over rather than the column
+    let key_column =
+        help.bindings.iter().map(|(action, chords)| keys(&split(action, chords).0).width()).max().unwrap_or(0).min(22);
+    let usage = |spec: &commands::CommandSpec| format!("/{} {}", spec.name, spec.usage);
+    let command_column = COMMANDS.iter().map(|spec| usage(spec).width()).max().unwrap_or(0);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for context in Context::ALL {
+        lines.push(heading(context.title()));
+        for (action, chords) in help.bindings.iter().filter(|(action, _)| action.context() == context) {
+            let (anywhere, outside_input) = split(action, chords);
+            let key_style = if anywhere.is_empty() { theme.muted } else { theme.key };
+            let mut spans = vec![
+                Span::styled(
+                    format!("  {:width$}  ", keys(&anywhere), width = key_column),
+                    Style::default().fg(key_style).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(action.label(), Style::default().fg(theme.secondary)),
+            ];
+            if !outside_input.is_empty() {
+                spans.push(Span::styled(
+                    format!(" ({} outside the input box)", keymap::describe(&outside_input)),
+                    Style::default().fg(theme.secondary),
+                ));
+            }
+            spans.push(Span::styled(format!("  {}", action.name()), Style::default().fg(theme.muted)));
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::default());
+    }
+
+    if help.vim {
+        lines.push(heading("Vim normal mode (Esc leaves insert and visual mode, then cancels)"));
+        for (keys, about) in vim::HELP {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:width$}  ", keys, width = key_column),
+                    Style::default().fg(theme.key).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(*about, Style::default().fg(theme.secondary)),
+            ]));
+        }
+        lines.push(Line::default());
+    }
+
+    lines.push(heading("Slash commands"));
+    for spec in COMMANDS {
+        let mut spans = vec![
+            Span::styled(format!("  /{}", spec.name), Style::default().fg(theme.key).add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!(" {:width$}  ", spec.usage, width = command_column - spec.name.width() - 1),
+                Style::default().fg(theme.text),
+            ),
+            Span::styled(spec.about, Style::default().fg(theme.secondary)),
+        ];
+        if !spec.aliases.is_empty() {
+            let aliases: Vec<String> = spec.aliases.iter().map(|alias| format!("/{}", alias)).collect();
+            spans.push(Span::styled(format!(" (also {})", aliases.join(", ")), Style::default().fg(theme.muted)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let max_scroll = lines.len().saturating_sub(area.height.saturating_sub(2) as usize);
+    help.max_scroll.set(max_scroll);
+    let scroll = help.scroll.min(max_scroll);
+    f.render_widget(Paragraph::new(lines).block(block).scroll((scroll as u16, 0)), area);
+}
+
+/// A rectangle of the given percentage size centered in `area`.
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+fn render_sidebar(f: &mut Frame, area: Rect, sidebar: &Sidebar, current_id: &str, theme: &Theme) {
+    let border_color = if sidebar.focused { theme.accent } else { theme.muted };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Conversations")
+        .border_style(Style::default().fg(border_color))
+        .style(Style::default().bg(theme.background));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    // Filter box
+    let filter_line = if sidebar.mode == SidebarMode::Filter {
+        Line::from(vec![
+            Span::styled("🔍 ", Style::default().fg(theme.busy)),
+            Span::styled(format!("{}▏", sidebar.filter), Style::default().fg(theme.text)),
+        ])
+    } else if sidebar.filter.is_empty() {
+        Line::from(Span::styled("🔍 / to filter", Style::default().fg(theme.muted)))
+    } else {
+        Line::from(vec![
+            Span::styled("🔍 ", Style::default().fg(theme.busy)),
+            Span::styled(sidebar.filter.clone(), Style::default().fg(theme.secondary)),
+        ])
+    };
+    f.render_widget(Paragraph::new(filter_line), rows[0]);
+
+    // Session list
+    let title_width = rows[1].width.saturating_sub(4) as usize;
+    let visible = sidebar.visible();
+    let items: Vec<ListItem> = visible
+        .iter()
+        .enumerate()
+        .map(|(i, session)| {
+            let title = match &sidebar.mode {
+                SidebarMode::Rename(draft) if i == sidebar.selected => format!("{}▏", draft),
+                _ => truncate_to_width(&session.title, title_width),
+            };
+            let title_style = if session.id == current_id {
+                Style::default().fg(theme.ok).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+            };
+            ListItem::new(vec![
+                Line::from(Span::styled(title, title_style)),
+                Line::from(Span::styled(
+                    format!("{} · {} msgs", format_date(&session.updated), session.message_count),
+                    Style::default().fg(theme.muted),
+                )),
+            ])
+        })
+        .collect();
+
+    if items.is_empty() {
+        let empty = if sidebar.sessions.is_empty() { "No saved conversations" } else { "No matches" };
+        f.render_widget(
+            Paragraph::new(empty).style(Style::default().fg(theme.muted)),
+            rows[1],
+        );
+    } else {
+        let list = List::new(items)
+            .highlight_style(theme.highlight_style())
+            .highlight_symbol("▶ ");
+        let mut list_state = ListState::default().with_selected(Some(sidebar.selected));
+        f.render_stateful_widget(list, rows[1], &mut list_state);
+    }
+
+    // Key hints for the current mode
+    let hint = match sidebar.mode {
+        SidebarMode::Browse => "⏎ open  r rename  d delete",
+        SidebarMode::Filter => "⏎ done  Esc clear",
+        SidebarMode::Rename(_) => "⏎ save  Esc cancel",
+        SidebarMode::ConfirmDelete => "Delete? y / n",
+    };
+    let hint_color = if sidebar.mode == SidebarMode::ConfirmDelete { theme.error } else { theme.muted };
+    f.render_widget(
+        Paragraph::new(hint).style(Style::default().fg(hint_color)),
+        rows[2],
+    );
+}
+
+fn render_model_picker(f: &mut Frame, picker: &ModelPicker, current_model: &str, theme: &Theme) {
+    let area = centered_rect(70, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Select a model (↑/↓, Enter to switch, Esc to close) ")
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.background));
+
+    let Some(models) = &picker.models else {
+        let loading = Paragraph::new("Fetching models...")
+            .style(Style::default().fg(theme.busy))
+            .block(block);
+        f.render_widget(loading, area);
+        return;
+    };
+
+    let items: Vec<ListItem> = models
+        .iter()
+        .map(|model| {
+            let marker = if model.id() == current_model { "● " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(theme.ok)),
+                Span::styled(
+                    model.id().to_string(),
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("  {}", model.display_name),
+                    Style::default().fg(theme.secondary),
+                ),
+                Span::styled(
+                    format!("  in {} / out {} tokens", model.input_token_limit, model.output_token_limit),
+                    Style::default().fg(theme.muted),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.highlight_style())
+        .highlight_symbol("▶ ");
+
+    let mut list_state = ListState::default().with_selected(Some(picker.selected));
+    f.render_stateful_widget(list, area, &mut list_state);
 }
 
-fn render_title(f: &mut Frame, area: Rect, frame: usize) {
-    let rainbow_colors = [
-        Color::Red,
-        Color::Yellow,
-        Color::Green,
-        Color::Cyan,
-        Color::Blue,
-        Color::Magenta,
-    ];
+fn render_title(f: &mut Frame, area: Rect, frame: usize, theme: &Theme) {
+    let rainbow_colors = match theme.title.as_slice() {
+        [] => std::slice::from_ref(&theme.text),
+        colors => colors,
+    };
     
     let title_text = "GEMINI CHAT TUI";
     let mut spans = Vec::new();
@@ -138,234 +802,331 @@ fn render_title(f: &mut Frame, area: Rect, frame: usize) {
         .block(Block::default().borders(Borders::ALL).border_style(
             Style::default().fg(rainbow_colors[frame % rainbow_colors.len()])
         ))
-        .style(Style::default().bg(Color::Black))
+        .style(Style::default().bg(theme.background))
         .wrap(Wrap { trim: true });
     
     f.render_widget(title, area);
 }
 
+const LOADING_CHARS: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 fn render_chat_area(f: &mut Frame, area: Rect, app: &AppState) {
-    let mut items = Vec::new();
+    let theme = &app.theme;
+    let mut chat_lines: Vec<Line> = Vec::new();
+    let loading_char = if app.reduced_motion {
+        "…"
+    } else {
+        LOADING_CHARS[app.animation_frame % LOADING_CHARS.len()]
+    };
     
-    for (_i, message) in app.messages.iter().enumerate() {
+    // Bubbles span the whole inner width; the scrollbar sits on the border
+    let avail = area.width.saturating_sub(2) as usize;
+    
+    let mut message_rows = Vec::with_capacity(app.messages.len());
+    
+    for (i, message) in app.messages.iter().enumerate() {
+        let start = chat_lines.len();
+        let selection = app.selection.filter(|selection| selection.message == i);
+        let selected_code = selection.and_then(|selection| selection.code_block);
         let timestamp = format_timestamp(&message.timestamp);
+        let is_streaming = app.streaming && i + 1 == app.messages.len();
+        let status_suffix = if message.cancelled { " (cancelled)" } else { "" };
         
-        if message.is_user {
+        if message.role == Role::User {
             // User message (right-aligned, blue bubble)
-            let max_width = area.width.saturating_sub(10) as usize; // More conservative width
-            let wrapped_content = wrap_text(&message.content, max_width);
-            
-            // Calculate the width needed for this bubble
-            let content_width = wrapped_content.iter()
-                .map(|line| format!("You: {}", line).width())
-                .max()
-                .unwrap_or(10)
-                .min(max_width);
-            
-            let bubble_width = content_width + 4; // Add padding
-            let timestamp_header = format!("You {}", timestamp);
-            let header_width = timestamp_header.width() + 4;
-            let actual_width = bubble_width.max(header_width).min(max_width + 4);
-            
-            // Create top border
-            let top_border = format!("╭─ {} {}╮", 
-                timestamp_header,
-                "─".repeat(actual_width.saturating_sub(timestamp_header.width() + 5))
-            );
-            
-            let mut lines = vec![
-                Line::from(vec![
-                    Span::raw(" ".repeat(area.width.saturating_sub(actual_width as u16 + 2) as usize)),
-                    Span::styled(top_border, Style::default().fg(Color::Cyan)),
-                ]),
-            ];
-            
-            // Add content lines with markdown parsing
-            for line in wrapped_content {
-                let content_prefix = "You: ";
-                let right_padding_size = actual_width.saturating_sub(content_prefix.width() + line.width() + 2);
-                let right_padding = " ".repeat(right_padding_size);
-                let left_padding = " ".repeat(area.width.saturating_sub(actual_width as u16 + 2) as usize);
-                
-                let mut line_spans = vec![
-                    Span::raw(left_padding),
-                    Span::styled("│ ", Style::default().fg(Color::Cyan)),
-                    Span::styled(content_prefix, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
-                ];
-                
-                // Parse markdown for user messages too
-                line_spans.extend(parse_markdown_spans(&line));
-                
-                line_spans.push(Span::raw(right_padding));
-                line_spans.push(Span::styled(" │", Style::default().fg(Color::Cyan)));
-                
-                lines.push(Line::from(line_spans));
-            }
-            
-            // Create bottom border
-            let bottom_border = format!("╰{}╯", "─".repeat(actual_width));
-            lines.push(Line::from(vec![
-                Span::raw(" ".repeat(area.width.saturating_sub(actual_width as u16 + 2) as usize)),
-                Span::styled(bottom_border, Style::default().fg(Color::Cyan)),
-            ]));
-            lines.push(Line::from(""));
-            
-            items.push(ListItem::new(lines));
+            let border = bubble_style(theme, message.role, selection.is_some(), message.cancelled);
+            let prefix = "You: ";
+            let max_width = avail.saturating_sub(10 + prefix.width());
+            let content = markdown::render(&message.content, max_width, selected_code, theme)
+                .into_iter()
+                .enumerate()
+                .map(|(row, line)| {
+                    let lead = if row == 0 { prefix.to_string() } else { " ".repeat(prefix.width()) };
+                    let mut spans = vec![Span::styled(lead, Style::default().fg(theme.text).add_modifier(Modifier::BOLD))];
+                    spans.extend(line.spans);
+                    Line::from(spans)
+                })
+                .collect();
+            let header = format!("You {}{}", timestamp, status_suffix);
+            chat_lines.extend(bubble(&header, content, border, true, avail));
         } else {
             // Gemini message (left-aligned, green bubble)
-            let max_width = area.width.saturating_sub(8) as usize; // More conservative width
-            let wrapped_content = wrap_text(&message.content, max_width);
+            let border = bubble_style(theme, message.role, selection.is_some(), message.cancelled);
+            let content = markdown::render(&message.content, avail.saturating_sub(8), selected_code, theme);
             
-            // Calculate the width needed for this bubble
-            let content_width = wrapped_content.iter()
-                .map(|line| line.width())
-                .max()
-                .unwrap_or(10)
-                .min(max_width);
-            
-            let timestamp_header = format!("🤖 Gemini {}", timestamp);
-            let header_width = timestamp_header.width() + 4;
-            let actual_width = content_width.max(header_width).min(max_width);
-            
-            // Create top border
-            let top_border = format!("╭─ {} {}╮",
-                timestamp_header,
-                "─".repeat(actual_width.saturating_sub(timestamp_header.width() + 5))
-            );
-            
-            let mut lines = vec![
-                Line::from(vec![
-                    Span::styled(top_border, Style::default().fg(Color::Green)),
-                ]),
-            ];
-            
-            // Add content lines with markdown parsing
-            for line in wrapped_content {
-                let padding_size = actual_width.saturating_sub(line.width() + 2);
-                let padding = " ".repeat(padding_size);
-                
-                let mut line_spans = vec![
-                    Span::styled("│ ", Style::default().fg(Color::Green)),
-                ];
-                
-                // Parse markdown and add spans
-                line_spans.extend(parse_markdown_spans(&line));
-                
-                // Add padding and closing border
-                line_spans.push(Span::raw(padding));
-                line_spans.push(Span::styled(" │", Style::default().fg(Color::Green)));
-                
-                lines.push(Line::from(line_spans));
-            }
-            
-            // Create bottom border
-            let bottom_border = format!("╰{}╯", "─".repeat(actual_width));
-            lines.push(Line::from(vec![
-                Span::styled(bottom_border, Style::default().fg(Color::Green)),
-            ]));
-            lines.push(Line::from(""));
-            
-            items.push(ListItem::new(lines));
+            let speaker = match &message.persona {
+                Some(persona) => format!("🤖 Gemini ({})", persona),
+                None => "🤖 Gemini".to_string(),
+            };
+            let header = if is_streaming {
+                format!("{} {} {}", speaker, timestamp, loading_char)
+            } else {
+                format!("{} {}{}", speaker, timestamp, status_suffix)
+            };
+            chat_lines.extend(bubble(&header, content, border, false, avail));
         }
+        message_rows.push(start..chat_lines.len());
     }
+    app.message_rows.replace(message_rows);
     
-    // Add loading animation if waiting for response
-    if app.is_loading {
-        let loading_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-        let loading_char = loading_chars[app.animation_frame % loading_chars.len()];
-        
+    // Add loading animation until the first streamed chunk arrives
+    if app.is_loading && !app.streaming {
         let loading_text = format!("{} Processing your message...", loading_char);
-        let timestamp_header = "Gemini is thinking...";
-        let content_width = loading_text.width().max(timestamp_header.width());
-        let actual_width = content_width + 4;
-        
-        // Create top border
-        let top_border = format!("╭─ {} {}╮",
-            timestamp_header,
-            "─".repeat(actual_width.saturating_sub(timestamp_header.width() + 5))
+        let content = vec![Line::from(Span::styled(
+            loading_text,
+            Style::default().fg(theme.busy).add_modifier(Modifier::BOLD),
+        ))];
+        chat_lines.extend(bubble("Gemini is thinking...", content, Style::default().fg(theme.busy), false, avail));
+    }
+    
+    let chat_title = match &app.persona {
+        Some(persona) => format!("Chat · 🎭 {}", persona),
+        None => "Chat".to_string(),
+    };
+    
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(chat_title)
+        .border_style(Style::default().fg(theme.border));
+    let inner = block.inner(area);
+    
+    // Remember the geometry so scrolling can be clamped between frames
+    let viewport = inner.height as usize;
+    let max_scroll = chat_lines.len().saturating_sub(viewport);
+    app.chat_viewport.set(ChatViewport { height: viewport, max_scroll });
+    let top = if app.follow_bottom { max_scroll } else { app.scroll_offset.min(max_scroll) };
+    
+    let chat = Paragraph::new(chat_lines)
+        .block(block)
+        .style(Style::default().bg(theme.background))
+        .scroll((top.min(u16::MAX as usize) as u16, 0));
+    
+    f.render_widget(chat, area);
+    
+    if max_scroll > 0 {
+        let mut scrollbar_state = ScrollbarState::new(max_scroll)
+            .position(top)
+            .viewport_content_length(viewport);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some("▲"))
+            .end_symbol(Some("▼"))
+            .style(Style::default().fg(theme.muted));
+        f.render_stateful_widget(
+            scrollbar,
+            area.inner(Margin { vertical: 1, horizontal: 0 }),
+            &mut scrollbar_state,
         );
-        
-        // Create content line with padding
-        let padding_size = actual_width.saturating_sub(loading_text.width() + 2);
-        let padding = " ".repeat(padding_size);
-        
-        // Create bottom border
-        let bottom_border = format!("╰{}╯", "─".repeat(actual_width));
-        
-        items.push(ListItem::new(vec![
-            Line::from(vec![
-                Span::styled(top_border, Style::default().fg(Color::Yellow)),
-            ]),
-            Line::from(vec![
-                Span::styled("│ ", Style::default().fg(Color::Yellow)),
-                Span::styled(loading_text, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                Span::raw(padding),
-                Span::styled(" │", Style::default().fg(Color::Yellow)),
-            ]),
-            Line::from(vec![
-                Span::styled(bottom_border, Style::default().fg(Color::Yellow)),
-            ]),
-            Line::from(""),
-        ]));
     }
     
-    let chat_list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Chat")
-                .border_style(Style::default().fg(Color::White))
-        )
-        .style(Style::default().bg(Color::Black));
+    // Let the user know the conversation moved on while they read history
+    if !app.follow_bottom && app.unseen_messages && inner.height > 0 {
+        let notice = " ↓ New messages below (End) ";
+        let notice_width = (notice.width() as u16).min(inner.width);
+        let notice_area = Rect {
+            x: inner.x + inner.width.saturating_sub(notice_width) / 2,
+            y: inner.y + inner.height - 1,
+            width: notice_width,
+            height: 1,
+        };
+        f.render_widget(Clear, notice_area);
+        f.render_widget(
+            Paragraph::new(notice)
+                .style(theme.badge_style(theme.busy).add_modifier(Modifier::BOLD)),
+            notice_area,
+        );
+    }
+}
+
+/// Border of a message bubble. Without colors your messages are bold,
+/// Gemini's plain, the one picked for copying underlined and cancelled
+/// ones dim.
+fn bubble_style(theme: &Theme, role: Role, selected: bool, cancelled: bool) -> Style {
+    if theme.monochrome {
+        let mut style = Style::default();
+        if role == Role::User {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if selected {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        } else if cancelled {
+            style = style.add_modifier(Modifier::DIM);
+        }
+        return style;
+    }
+    let color = if selected {
+        theme.selected
+    } else if cancelled {
+        theme.muted
+    } else if role == Role::User {
+        theme.user
+    } else {
+        theme.model
+    };
+    Style::default().fg(color)
+}
+
+/// Frames `content` in a rounded bubble with `header` set into the top
+/// border, pushed to the right edge of `avail` columns when `align_right`.
+fn bubble(
+    header: &str,
+    mut content: Vec<Line<'static>>,
+    border: Style,
+    align_right: bool,
+    avail: usize,
+) -> Vec<Line<'static>> {
+    if content.is_empty() {
+        content.push(Line::default());
+    }
+    let header = truncate_to_width(header, avail.saturating_sub(6));
+    let inner = content
+        .iter()
+        .map(Line::width)
+        .max()
+        .unwrap_or(0)
+        .max(header.width() + 2);
+    let indent = if align_right { " ".repeat(avail.saturating_sub(inner + 4)) } else { String::new() };
     
-    f.render_widget(chat_list, area);
+    let mut lines = vec![Line::from(vec![
+        Span::raw(indent.clone()),
+        Span::styled(format!("╭─ {} {}╮", header, "─".repeat(inner - header.width() - 1)), border),
+    ])];
+    for line in content {
+        let padding = " ".repeat(inner - line.width());
+        let mut spans = vec![Span::raw(indent.clone()), Span::styled("│ ", border)];
+        spans.extend(line.spans);
+        spans.push(Span::raw(padding));
+        spans.push(Span::styled(" │", border));
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(vec![
+        Span::raw(indent),
+        Span::styled(format!("╰{}╯", "─".repeat(inner + 2)), border),
+    ]));
+    lines.push(Line::from(""));
+    lines
 }
 
 fn render_input_area(f: &mut Frame, area: Rect, app: &AppState) {
-    let input_text = if app.input.is_empty() {
-        "Type your message here... (Press Enter to send, Ctrl+C to quit)"
-    } else {
-        &app.input
+    // During Ctrl+R the box previews the matching prompt instead
+    let mut search_preview = TextArea::default();
+    let (title, input) = match &app.history_search {
+        Some(search) => {
+            search_preview.set_text(&search.preview);
+            let label = if search.found.is_some() || search.query.is_empty() {
+                "reverse-i-search"
+            } else {
+                "failing reverse-i-search"
+            };
+            (format!("Your Message · ({})`{}'", label, search.query), &search_preview)
+        }
+        None => ("Your Message".to_string(), &app.input),
     };
     
-    let input_style = if app.input.is_empty() {
-        Style::default().fg(Color::DarkGray)
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(app.theme.accent));
+    let inner = block.inner(area);
+    
+    let (rows, (cursor_x, cursor_y)) = input.wrapped(inner.width as usize);
+    
+    // Keep the cursor row in view once the text outgrows the box
+    let visible_rows = (inner.height as usize).max(1);
+    let first_row = (cursor_y + 1).saturating_sub(visible_rows);
+    
+    let paragraph = if input.is_empty() && app.history_search.is_none() {
+        Paragraph::new(app.input_placeholder.as_str())
+            .style(Style::default().fg(app.theme.muted))
     } else {
-        Style::default().fg(Color::White)
+        let chips: Vec<&str> = input.paste_chips().collect();
+        let selected = input.selected_in_rows(inner.width as usize);
+        let lines: Vec<Line> = rows
+            .into_iter()
+            .zip(selected)
+            .skip(first_row)
+            .take(visible_rows)
+            .map(|(row, selected)| match selected {
+                Some(range) => Line::from(vec![
+                    Span::raw(row[..range.start].to_string()),
+                    Span::styled(row[range.clone()].to_string(), Style::default().add_modifier(Modifier::REVERSED)),
+                    Span::raw(row[range.end..].to_string()),
+                ]),
+                None => highlight_paste_chips(row, &chips, &app.theme),
+            })
+            .collect();
+        Paragraph::new(lines).style(Style::default().fg(app.theme.text))
     };
     
-    let input = Paragraph::new(input_text)
-        .style(input_style)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Your Message")
-                .border_style(Style::default().fg(Color::Magenta))
-        );
+    f.render_widget(paragraph.block(block), area);
     
-    f.render_widget(input, area);
-    
-    // Render cursor
-    if !app.input.is_empty() {
-        let cursor_x = area.x + 1 + app.input_cursor as u16;
-        let cursor_y = area.y + 1;
+    // Render cursor unless another panel has the keyboard
+    let input_focused = app.model_picker.is_none()
+        && !app.sidebar.as_ref().is_some_and(|sidebar| sidebar.focused);
+    if input_focused {
+        let cursor_x = inner.x + (cursor_x as u16).min(inner.width.saturating_sub(1));
+        let cursor_y = inner.y + (cursor_y - first_row) as u16;
         f.set_cursor_position((cursor_x, cursor_y));
     }
 }
 
+/// Styles `[pasted N lines]` chips so they read as a single token.
+fn highlight_paste_chips(row: String, chips: &[&str], theme: &Theme) -> Line<'static> {
+    let chip_style = theme.badge_style(theme.accent);
+    let mut spans = Vec::new();
+    let mut rest = row.as_str();
+
+    while let Some((index, chip)) = chips
+        .iter()
+        .filter_map(|chip| rest.find(chip).map(|index| (index, *chip)))
+        .min_by_key(|(index, _)| *index)
+    {
+        if index > 0 {
+            spans.push(Span::raw(rest[..index].to_string()));
+        }
+        spans.push(Span::styled(chip.to_string(), chip_style));
+        rest = &rest[index + chip.len()..];
+    }
+    if !rest.is_empty() {
+        spans.push(Span::raw(rest.to_string()));
+    }
+
+    Line::from(spans)
+}
+
 fn render_status_bar(f: &mut Frame, area: Rect, app: &AppState) {
+    let theme = &app.theme;
     let status_color = if app.is_loading {
-        Color::Yellow
+        theme.busy
     } else {
-        Color::Green
+        theme.ok
     };
     
+    let mut title = vec![Span::raw("Status")];
+    if let Some(vim) = &app.vim {
+        let mode_color = match vim.mode {
+            Mode::Normal => theme.vim_normal,
+            Mode::Insert => theme.vim_insert,
+            Mode::Visual => theme.vim_visual,
+        };
+        title.push(Span::raw(" "));
+        title.push(Span::styled(
+            format!(" {} ", vim.mode.label()),
+            theme.badge_style(mode_color).add_modifier(Modifier::BOLD),
+        ));
+        if !vim.pending().is_empty() {
+            title.push(Span::styled(format!(" {}", vim.pending()), Style::default().fg(theme.busy)));
+        }
+    }
+
     let status = Paragraph::new(app.status_message.as_str())
         .style(Style::default().fg(status_color).add_modifier(Modifier::BOLD))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Status")
+                .title(Line::from(title))
+                .title_top(
+                    Line::from(format!(" 🤖 {} · {} ", app.model, app.generation.summary())).right_aligned(),
+                )
                 .border_style(Style::default().fg(status_color))
         );
     
@@ -386,120 +1147,85 @@ fn format_timestamp(timestamp: &std::time::SystemTime) -> String {
     }
 }
 
-fn wrap_text(text: &str, width: usize) -> Vec<String> {
-    let mut lines = Vec::new();
-    let mut current_line = String::new();
-    
-    // Handle minimum width to prevent issues
-    let min_width = 10;
-    let actual_width = width.max(min_width);
-    
-    for word in text.split_whitespace() {
-        // Handle very long words by breaking them
-        if word.width() > actual_width {
-            // If we have content in current line, push it first
-            if !current_line.is_empty() {
-                lines.push(current_line);
-                current_line = String::new();
-            }
-            
-            // Break the long word into chunks
-            let mut remaining = word;
-            while !remaining.is_empty() {
-                let mut chunk_end = 0;
-                let mut chunk_width = 0;
-                
-                for (i, c) in remaining.char_indices() {
-                    let char_width = c.width().unwrap_or(0);
-                    if chunk_width + char_width > actual_width {
-                        break;
-                    }
-                    chunk_width += char_width;
-                    chunk_end = i + c.len_utf8();
-                }
-                
-                if chunk_end == 0 {
-                    // Single character is too wide, take it anyway
-                    chunk_end = remaining.chars().next().unwrap().len_utf8();
-                }
-                
-                let chunk = &remaining[..chunk_end];
-                lines.push(chunk.to_string());
-                remaining = &remaining[chunk_end..];
-            }
-        } else {
-            // Normal word processing
-            if current_line.is_empty() {
-                current_line = word.to_string();
-            } else if current_line.width() + 1 + word.width() <= actual_width {
-                current_line.push(' ');
-                current_line.push_str(word);
-            } else {
-                lines.push(current_line);
-                current_line = word.to_string();
-            }
+/// `YYYY-MM-DD HH:MM` in UTC, matching [`format_timestamp`].
+fn format_date(timestamp: &std::time::SystemTime) -> String {
+    use std::time::UNIX_EPOCH;
+
+    match timestamp.duration_since(UNIX_EPOCH) {
+        Ok(duration) => {
+            let secs = duration.as_secs();
+            let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+            format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}",
+                year,
+                month,
+                day,
+                (secs / 3600) % 24,
+                (secs / 60) % 60
+            )
         }
+        Err(_) => "????-??-??".to_string(),
     }
-    
-    if !current_line.is_empty() {
-        lines.push(current_line);
+}
+
+/// Cuts `text` to at most `width` columns, ending with `…` if shortened.
+fn truncate_to_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
     }
-    
-    if lines.is_empty() {
-        lines.push(String::new());
+
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        result.push(c);
+        used += char_width;
     }
-    
-    lines
+    result.push('…');
+    result
 }
 
-fn parse_markdown_spans(text: &str) -> Vec<Span<'static>> {
-    let mut spans = Vec::new();
-    let mut chars = text.chars().peekable();
-    let mut current_text = String::new();
-    
-    while let Some(ch) = chars.next() {
-        if ch == '*' && chars.peek() == Some(&'*') {
-            // Found start of bold text
-            chars.next(); // consume second *
-            
-            // Push any accumulated normal text
-            if !current_text.is_empty() {
-                spans.push(Span::styled(current_text.clone(), Style::default().fg(Color::White)));
-                current_text.clear();
-            }
-            
-            // Collect bold text until next **
-            let mut bold_text = String::new();
-            let mut found_end = false;
-            
-            while let Some(ch) = chars.next() {
-                if ch == '*' && chars.peek() == Some(&'*') {
-                    chars.next(); // consume second *
-                    found_end = true;
-                    break;
-                }
-                bold_text.push(ch);
-            }
-            
-            if found_end {
-                spans.push(Span::styled(
-                    bold_text,
-                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
-                ));
-            } else {
-                // No closing **, treat as regular text
-                current_text.push_str("**");
-                current_text.push_str(&bold_text);
-            }
-        } else {
-            current_text.push(ch);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roles(state: &AppState) -> Vec<String> {
+        state
+            .conversation()
+            .iter()
+            .map(|content| serde_json::to_value(content).unwrap()["role"].as_str().unwrap().to_string())
+            .collect()
     }
-    
-    // Push any remaining normal text
-    if !current_text.is_empty() {
-        spans.push(Span::styled(current_text, Style::default().fg(Color::White)));
+
+    #[test]
+    fn test_failed_turns_leave_the_conversation() {
+        let mut state = AppState::default();
+        state.add_message("first".to_string(), Role::User);
+        state.add_error("❌ Error: quota".to_string());
+        state.add_message("retry".to_string(), Role::User);
+        assert_eq!(roles(&state), vec!["user"]);
+
+        state.add_message("answer".to_string(), Role::Model);
+        state.add_message("follow-up".to_string(), Role::User);
+        assert_eq!(roles(&state), vec!["user", "model", "user"]);
+        assert_eq!(serde_json::to_value(&state.conversation()[0]).unwrap()["parts"][0]["text"], "retry");
     }
-    
-    spans
-}
\ No newline at end of file
+
+    #[test]
+    fn test_help_lists_typed_keys_apart() {
+        let help = HelpOverlay::new(crate::keymap::Keymap::default().bindings(), false);
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|f| render_help(f, &help, &Theme::default())).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        let help_row = rows.iter().find(|row| row.contains(" help")).unwrap();
+        assert!(help_row.contains("F1  "), "{}", help_row);
+        assert!(help_row.contains("(? outside the input box)"), "{}", help_row);
+    }
+}
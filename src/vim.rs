@@ -0,0 +1,575 @@
+use crate::editor::{Position, TextArea};
+
+/// Counts are capped here, so `999999999w` cannot hang the UI.
+const MAX_COUNT: usize = 9999;
+
+/// Which keys the input box takes in Vim mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Insert,
+    Visual,
+}
+
+impl Mode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+            Mode::Visual => "VISUAL",
+        }
+    }
+}
+
+/// The Vim keys [`Vim::key`] understands, for the help overlay.
+pub const HELP: &[(&str, &str)] = &[
+    ("i a I A o O", "Insert before / after the cursor, at line start / end, on a new line below / above"),
+    ("h j k l", "Move left, down, up, right"),
+    ("w b", "Next / previous word"),
+    ("0 ^ $", "Start of the line, first non-blank, end of the line"),
+    ("gg G", "First / last line"),
+    ("x X", "Delete the character under / before the cursor"),
+    ("d c y + motion", "Delete, change or yank, e.g. dw, c$, y2w"),
+    ("dd cc yy", "Delete, change or yank whole lines"),
+    ("diw ciw daw caw", "Delete or change a word"),
+    ("D C s", "Delete / change to the end of the line, change a character"),
+    ("p P", "Put what was deleted or yanked after / before the cursor"),
+    ("u", "Undo"),
+    ("v", "Visual mode: move, then d, c or y"),
+    ("2w 3dd …", "A count repeats a motion or command"),
+];
+
+/// Undo steps kept per input box.
+const MAX_UNDO: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward,
+    WordBackward,
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FirstLine,
+    LastLine,
+}
+
+impl Motion {
+    /// Up and down motions make an operator act on whole lines.
+    fn linewise(self) -> bool {
+        matches!(self, Motion::Up | Motion::Down | Motion::FirstLine | Motion::LastLine)
+    }
+
+    fn apply(self, input: &mut TextArea) {
+        let (row, col) = input.cursor();
+        match self {
+            // h and l stay on the line
+            Motion::Left if col > 0 => input.move_left(),
+            Motion::Right if col < input.line(row).len() => input.move_right(),
+            Motion::Left | Motion::Right => {}
+            Motion::Up => input.move_up(),
+            Motion::Down => input.move_down(),
+            Motion::WordForward => input.move_word_forward(),
+            Motion::WordBackward => input.move_word_left(),
+            Motion::LineStart => input.move_home(),
+            Motion::FirstNonBlank => input.move_first_non_blank(),
+            Motion::LineEnd => input.move_end(),
+            Motion::FirstLine => {
+                input.set_cursor((0, 0));
+                input.move_first_non_blank();
+            }
+            Motion::LastLine => {
+                input.set_cursor((input.line_count() - 1, 0));
+                input.move_first_non_blank();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Motion(Motion),
+    /// The operator doubled, as in `dd`.
+    Lines,
+    Word { around: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Move(Motion),
+    Operate(Operator, Target),
+    /// A one-key command such as `x`, `p` or `o`.
+    Key(char),
+}
+
+/// What an operator covers: characters up to an exclusive end, or lines.
+enum Extent {
+    Chars(Position, Position),
+    Lines(usize, usize),
+}
+
+/// How far a typed key sequence got.
+#[derive(Debug, PartialEq, Eq)]
+enum Step<T> {
+    Pending,
+    Invalid,
+    Done(T),
+}
+
+/// Vim-style modal editing over the input box. Starts in insert mode so
+/// typing works right away.
+#[derive(Debug, Clone)]
+pub struct Vim {
+    pub mode: Mode,
+    /// Keys of a command still being typed, e.g. `2d` or `ci`.
+    pending: String,
+    /// The last deleted or yanked text.
+    register: String,
+    /// The register holds whole lines, so it is put on lines of its own.
+    linewise: bool,
+    undo: Vec<TextArea>,
+}
+
+impl Default for Vim {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Insert,
+            pending: String::new(),
+            register: String::new(),
+            linewise: false,
+            undo: Vec::new(),
+        }
+    }
+}
+
+impl Vim {
+    pub fn pending(&self) -> &str {
+        &self.pending
+    }
+
+    /// Esc: drops a half-typed command or goes back to normal mode. Returns
+    /// `false` in plain normal mode, where Esc keeps its usual meaning.
+    pub fn escape(&mut self, input: &mut TextArea) -> bool {
+        if !self.pending.is_empty() {
+            self.pending.clear();
+            return true;
+        }
+        match self.mode {
+            Mode::Insert => {
+                self.mode = Mode::Normal;
+                Motion::Left.apply(input);
+                input.clamp_to_last_char();
+                true
+            }
+            Mode::Visual => {
+                self.leave_visual(input);
+                true
+            }
+            Mode::Normal => false,
+        }
+    }
+
+    /// A character typed in normal or visual mode.
+    pub fn key(&mut self, c: char, input: &mut TextArea) {
+        if self.mode == Mode::Visual && self.pending.is_empty() && self.visual_key(c, input) {
+            return;
+        }
+
+        self.pending.push(c);
+        let (count, command) = match parse(&self.pending) {
+            Step::Pending => return,
+            Step::Invalid => {
+                self.pending.clear();
+                return;
+            }
+            Step::Done(parsed) => parsed,
+        };
+        self.pending.clear();
+
+        match (self.mode, command) {
+            (_, Command::Move(motion)) => {
+                let count = if matches!(motion, Motion::FirstLine | Motion::LastLine) { 1 } else { count };
+                for _ in 0..count {
+                    motion.apply(input);
+                }
+            }
+            (Mode::Visual, _) => {}
+            (_, Command::Operate(operator, target)) => {
+                let extent = extent(operator, target, count, input);
+                self.operate(operator, extent, input);
+            }
+            (_, Command::Key(key)) => self.run_key(key, count, input),
+        }
+        if self.mode != Mode::Insert {
+            input.clamp_to_last_char();
+        }
+    }
+
+    /// `d`, `x`, `c`, `y` and `v` act on the selection at once.
+    fn visual_key(&mut self, c: char, input: &mut TextArea) -> bool {
+        let cursor = input.cursor();
+        let (start, end) = input.selection().unwrap_or((cursor, input.after(cursor)));
+        let operator = match c {
+            'd' | 'x' => Operator::Delete,
+            'c' => Operator::Change,
+            'y' => Operator::Yank,
+            'v' => {
+                self.leave_visual(input);
+                return true;
+            }
+            _ => return false,
+        };
+        self.leave_visual(input);
+        self.operate(operator, Extent::Chars(start, end), input);
+        if self.mode != Mode::Insert {
+            input.clamp_to_last_char();
+        }
+        true
+    }
+
+    /// Back to normal mode, dropping the selection.
+    pub fn leave_visual(&mut self, input: &mut TextArea) {
+        self.mode = Mode::Normal;
+        input.set_anchor(None);
+    }
+
+    fn run_key(&mut self, key: char, count: usize, input: &mut TextArea) {
+        let (row, col) = input.cursor();
+        let line_len = input.line(row).len();
+        match key {
+            'i' => self.insert(input),
+            'a' => {
+                self.insert(input);
+                Motion::Right.apply(input);
+            }
+            'I' => {
+                self.insert(input);
+                input.move_first_non_blank();
+            }
+            'A' => {
+                self.insert(input);
+                input.move_end();
+            }
+            'o' => {
+                self.insert(input);
+                input.move_end();
+                input.insert_newline();
+            }
+            'O' => {
+                self.insert(input);
+                input.move_home();
+                input.insert_newline();
+                input.move_up();
+            }
+            'x' if line_len > 0 => {
+                let mut end = (row, col);
+                for _ in 0..count {
+                    end = input.after(end).min((row, line_len));
+                }
+                self.operate(Operator::Delete, Extent::Chars((row, col), end), input);
+            }
+            'X' if col > 0 => {
+                for _ in 0..count {
+                    Motion::Left.apply(input);
+                }
+                let start = input.cursor();
+                self.operate(Operator::Delete, Extent::Chars(start, (row, col)), input);
+            }
+            'D' => self.operate(Operator::Delete, Extent::Chars((row, col), (row, line_len)), input),
+            'C' => self.operate(Operator::Change, Extent::Chars((row, col), (row, line_len)), input),
+            's' => {
+                let extent = extent(Operator::Change, Target::Motion(Motion::Right), count, input);
+                self.operate(Operator::Change, extent, input);
+            }
+            'p' | 'P' => self.put(key == 'P', count, input),
+            'u' => {
+                if let Some(previous) = self.undo.pop() {
+                    *input = previous;
+                }
+            }
+            'v' => {
+                self.mode = Mode::Visual;
+                input.set_anchor(Some(input.cursor()));
+            }
+            _ => {}
+        }
+    }
+
+    fn insert(&mut self, input: &TextArea) {
+        self.snapshot(input);
+        self.mode = Mode::Insert;
+    }
+
+    fn snapshot(&mut self, input: &TextArea) {
+        if self.undo.len() == MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(input.clone());
+    }
+
+    fn operate(&mut self, operator: Operator, extent: Extent, input: &mut TextArea) {
+        if operator != Operator::Yank {
+            self.snapshot(input);
+        }
+        match extent {
+            Extent::Chars(start, end) => {
+                self.linewise = false;
+                self.register = match operator {
+                    Operator::Yank => {
+                        input.set_cursor(start);
+                        input.text_range(start, end)
+                    }
+                    Operator::Delete | Operator::Change => input.delete_range(start, end),
+                };
+            }
+            Extent::Lines(first, last) => {
+                self.linewise = true;
+                let end = (last, input.line(last).len());
+                self.register = match operator {
+                    Operator::Yank => {
+                        input.set_cursor((first, 0));
+                        input.text_range((first, 0), end) + "\n"
+                    }
+                    // Changing lines keeps one empty line to type into
+                    Operator::Change => input.delete_range((first, 0), end) + "\n",
+                    Operator::Delete => {
+                        input.set_cursor((first, 0));
+                        input.delete_lines(last - first + 1)
+                    }
+                };
+            }
+        }
+        if operator == Operator::Change {
+            self.mode = Mode::Insert;
+        }
+    }
+
+    /// `p` and `P`: whole lines go below or above the cursor's line, other
+    /// text after or before the cursor.
+    fn put(&mut self, before: bool, count: usize, input: &mut TextArea) {
+        if self.register.is_empty() {
+            return;
+        }
+        self.snapshot(input);
+        let (row, col) = input.cursor();
+
+        if self.linewise {
+            let lines = self.register.trim_end_matches('\n');
+            let text = vec![lines; count].join("\n");
+            if before {
+                input.move_home();
+                input.insert_str(&format!("{}\n", text));
+                input.set_cursor((row, 0));
+            } else {
+                input.move_end();
+                input.insert_str(&format!("\n{}", text));
+                input.set_cursor((row + 1, 0));
+            }
+            input.move_first_non_blank();
+        } else {
+            if !before && col < input.line(row).len() {
+                input.move_right();
+            }
+            input.insert_str(&self.register.repeat(count));
+            input.move_left();
+        }
+    }
+}
+
+/// What `operator` with `target` covers from the cursor; the cursor is left
+/// where it was.
+fn extent(operator: Operator, target: Target, count: usize, input: &mut TextArea) -> Extent {
+    let from = input.cursor();
+    match target {
+        Target::Lines => Extent::Lines(from.0, (from.0 + count - 1).min(input.line_count() - 1)),
+        Target::Word { around } => {
+            let range = input.word_at_cursor(around);
+            Extent::Chars((from.0, range.start), (from.0, range.end))
+        }
+        Target::Motion(motion) => {
+            for _ in 0..count {
+                // `cw` changes to the end of the word, like `ce`
+                if operator == Operator::Change && motion == Motion::WordForward {
+                    input.move_word_right();
+                } else {
+                    motion.apply(input);
+                }
+            }
+            let mut to = input.cursor();
+            input.set_cursor(from);
+
+            if motion.linewise() {
+                return Extent::Lines(from.0.min(to.0), from.0.max(to.0));
+            }
+            // A word motion off the end of the line stops at the line's end
+            if motion == Motion::WordForward && to.0 > from.0 {
+                to = (from.0, input.line(from.0).len());
+            }
+            Extent::Chars(from.min(to), from.max(to))
+        }
+    }
+}
+
+/// Parses a complete command: an optional count, then a motion, an
+/// operator with its target, or a one-key command.
+fn parse(keys: &str) -> Step<(usize, Command)> {
+    let (count, rest) = split_count(keys);
+    match motion(rest) {
+        Step::Done(motion) => return Step::Done((count, Command::Move(motion))),
+        Step::Pending => return Step::Pending,
+        Step::Invalid => {}
+    }
+
+    let mut chars = rest.chars();
+    let operator = match chars.next() {
+        Some('d') => Operator::Delete,
+        Some('c') => Operator::Change,
+        Some('y') => Operator::Yank,
+        Some(key) if "xXiaIAoOpPuvDCs".contains(key) && chars.next().is_none() => {
+            return Step::Done((count, Command::Key(key)));
+        }
+        _ => return Step::Invalid,
+    };
+
+    let (inner, target) = split_count(&rest[1..]);
+    let count = count.saturating_mul(inner).min(MAX_COUNT);
+    let target = match target {
+        "" | "i" | "a" => return Step::Pending,
+        "iw" => Target::Word { around: false },
+        "aw" => Target::Word { around: true },
+        doubled if doubled == &rest[..1] => Target::Lines,
+        target => match motion(target) {
+            Step::Done(motion) => Target::Motion(motion),
+            Step::Pending => return Step::Pending,
+            Step::Invalid => return Step::Invalid,
+        },
+    };
+    Step::Done((count, Command::Operate(operator, target)))
+}
+
+fn motion(keys: &str) -> Step<Motion> {
+    Step::Done(match keys {
+        "" | "g" => return Step::Pending,
+        "h" => Motion::Left,
+        "l" => Motion::Right,
+        "j" => Motion::Down,
+        "k" => Motion::Up,
+        "w" => Motion::WordForward,
+        "b" => Motion::WordBackward,
+        "0" => Motion::LineStart,
+        "^" => Motion::FirstNonBlank,
+        "$" => Motion::LineEnd,
+        "gg" => Motion::FirstLine,
+        "G" => Motion::LastLine,
+        _ => return Step::Invalid,
+    })
+}
+
+/// Splits a leading count off `keys`; a lone `0` is a motion, not a count.
+fn split_count(keys: &str) -> (usize, &str) {
+    let digits = keys
+        .char_indices()
+        .take_while(|(index, c)| c.is_ascii_digit() && (*index > 0 || *c != '0'))
+        .count();
+    if digits == 0 {
+        return (1, keys);
+    }
+    let count = keys[..digits]
+        .bytes()
+        .fold(0usize, |count, digit| count.saturating_mul(10).saturating_add(usize::from(digit - b'0')));
+    (count.min(MAX_COUNT), &keys[digits..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Puts `text` in a fresh input box in normal mode, cursor on the first
+    /// character, then types `keys`.
+    fn run(text: &str, keys: &str) -> (TextArea, Vim) {
+        let mut input = TextArea::default();
+        input.set_text(text);
+        input.set_cursor((0, 0));
+        let mut vim = Vim { mode: Mode::Normal, ..Vim::default() };
+        for key in keys.chars() {
+            match key {
+                '\x1b' => {
+                    vim.escape(&mut input);
+                }
+                key if vim.mode == Mode::Insert => input.insert_char(key),
+                key => vim.key(key, &mut input),
+            }
+        }
+        (input, vim)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("3w"), Step::Done((3, Command::Move(Motion::WordForward))));
+        assert_eq!(parse("0"), Step::Done((1, Command::Move(Motion::LineStart))));
+        assert_eq!(parse("2d3w"), Step::Done((6, Command::Operate(Operator::Delete, Target::Motion(Motion::WordForward)))));
+        assert_eq!(parse("dd"), Step::Done((1, Command::Operate(Operator::Delete, Target::Lines))));
+        assert_eq!(parse("ci"), Step::Pending);
+        assert_eq!(parse("ciw"), Step::Done((1, Command::Operate(Operator::Change, Target::Word { around: false }))));
+        assert_eq!(parse("g"), Step::Pending);
+        assert_eq!(parse("dz"), Step::Invalid);
+        assert_eq!(parse("xx"), Step::Invalid);
+
+        // Huge counts neither overflow nor run for ages
+        assert_eq!(parse("99999999999d99999999999w"), Step::Done((MAX_COUNT, Command::Operate(Operator::Delete, Target::Motion(Motion::WordForward)))));
+        assert_eq!(parse("99999999999999999999999x"), Step::Done((MAX_COUNT, Command::Key('x'))));
+        assert_eq!(run("one two", "999999999w").0.cursor(), (0, 6));
+    }
+
+    #[test]
+    fn test_motions_and_deletes() {
+        assert_eq!(run("let answer = 42;", "wdw").0.text(), "let = 42;");
+        assert_eq!(run("let answer = 42;", "$x").0.text(), "let answer = 42");
+        assert_eq!(run("let answer = 42;", "wD").0.text(), "let ");
+        assert_eq!(run("let answer = 42;", "2wd0").0.text(), "= 42;");
+        assert_eq!(run("one two", "wdw").0.text(), "one ");
+        assert_eq!(run("one\ntwo\nthree", "jdd").0.text(), "one\nthree");
+        assert_eq!(run("one\ntwo\nthree", "2dd").0.text(), "three");
+        assert_eq!(run("one\ntwo\nthree", "dj").0.text(), "three");
+    }
+
+    #[test]
+    fn test_change_insert_and_undo() {
+        let (input, vim) = run("say hello there", "wciwbye\x1b");
+        assert_eq!(input.text(), "say bye there");
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(input.cursor(), (0, 6));
+
+        assert_eq!(run("say hello there", "wcwhi\x1b").0.text(), "say hi there");
+        assert_eq!(run("say hello there", "wdaw").0.text(), "say there");
+        assert_eq!(run("abc", "A!\x1bI> \x1b").0.text(), "> abc!");
+        assert_eq!(run("one", "otwo\x1bOzero\x1b").0.text(), "one\nzero\ntwo");
+        assert_eq!(run("say hello there", "wdwdwu").0.text(), "say there");
+        assert_eq!(run("say hello there", "wdwdwuu").0.text(), "say hello there");
+    }
+
+    #[test]
+    fn test_yank_put_and_visual() {
+        assert_eq!(run("one\ntwo", "yyjp").0.text(), "one\ntwo\none");
+        assert_eq!(run("one\ntwo", "ddp").0.text(), "two\none");
+        assert_eq!(run("ab", "xp").0.text(), "ba");
+        assert_eq!(run("hello world", "vllld").0.text(), "o world");
+        assert_eq!(run("hello world", "wvly").1.register, "wo");
+        assert_eq!(run("abcd", "$2X").0.text(), "ad");
+        assert_eq!(run("hello world", "wv$y$p").0.text(), "hello worldworld");
+
+        let (input, vim) = run("hello world", "wvlc");
+        assert_eq!(input.text(), "hello rld");
+        assert_eq!(vim.mode, Mode::Insert);
+    }
+}